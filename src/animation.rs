@@ -0,0 +1,239 @@
+// Tools for watching time-stepped simulations evolve, either redrawn in place in the terminal or
+// exported as numbered frames (text, or images given a palette) to be stitched together elsewhere.
+use std::time::Duration;
+
+pub const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
+// A state that evolves in discrete steps, e.g. carts moving or a round of combat
+pub trait Simulation {
+    // Advances one step, or returns false (leaving the state unchanged) if the simulation is over
+    fn step(&mut self) -> bool;
+
+    fn render(&self) -> String;
+}
+
+// Renders the initial state and then each step, until the simulation finishes or the limit is hit
+pub struct Frames<'a, S> {
+    sim: &'a mut S,
+    remaining: usize,
+    started: bool,
+}
+
+impl<'a, S: Simulation> Frames<'a, S> {
+    pub fn new(sim: &'a mut S, max_frames: usize) -> Frames<'a, S> {
+        Frames { sim, remaining: max_frames, started: false }
+    }
+}
+
+impl<'a, S: Simulation> Iterator for Frames<'a, S> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if self.remaining == 0 || (self.started && !self.sim.step()) {
+            return None;
+        }
+        self.started = true;
+        self.remaining -= 1;
+        Some(self.sim.render())
+    }
+}
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter().position(|a| a == flag)
+        .map(|i| args.get(i+1).unwrap_or_else(|| panic!("{} requires a value", flag)).as_str())
+}
+
+mod exporter {
+    use super::{flag_value, Frames, Simulation};
+    use std::fs;
+    use std::io::{self, Write};
+    use std::path::{Path, PathBuf};
+    use std::thread;
+    use std::time::Duration;
+    use crate::image::{Image, Palette};
+
+    const CLEAR_SCREEN: &str = "\x1B[2J\x1B[H";
+
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub enum Target {
+        Terminal,
+        Directory(PathBuf),
+    }
+
+    pub struct Exporter {
+        target: Target,
+        max_frames: usize,
+        delay: Duration,
+        palette: Option<(Palette<char>, usize)>,
+    }
+
+    impl Exporter {
+        pub fn new(target: Target) -> Exporter {
+            Exporter { target, max_frames: 1000, delay: super::DEFAULT_DELAY, palette: None }
+        }
+
+        // Supports --animate (in the terminal) or --frames DIR, along with --delay MILLIS and
+        // --max-frames N. Returns None if neither --animate nor --frames was passed.
+        pub fn from_args(args: &[String]) -> Option<Exporter> {
+            let target = match flag_value(args, "--frames") {
+                Some(dir) => Target::Directory(dir.into()),
+                None if args.iter().any(|a| a == "--animate") => Target::Terminal,
+                None => return None,
+            };
+            let mut exporter = Exporter::new(target);
+            if let Some(delay) = flag_value(args, "--delay") {
+                exporter = exporter.delay(Duration::from_millis(delay.parse().expect("Invalid delay")));
+            }
+            if let Some(max_frames) = flag_value(args, "--max-frames") {
+                exporter = exporter.max_frames(max_frames.parse().expect("Invalid frame count"));
+            }
+            Some(exporter)
+        }
+
+        pub fn max_frames(mut self, max_frames: usize) -> Exporter {
+            self.max_frames = max_frames;
+            self
+        }
+
+        // Pause between frames in the terminal; ignored when writing frames to disk
+        pub fn delay(mut self, delay: Duration) -> Exporter {
+            self.delay = delay;
+            self
+        }
+
+        // Frames written to disk will be images, with each character scaled to a square of pixels
+        pub fn palette(mut self, palette: Palette<char>, scale: usize) -> Exporter {
+            self.palette = Some((palette, scale));
+            self
+        }
+
+        // Returns the number of frames shown or written
+        pub fn run<S: Simulation>(&self, sim: &mut S) -> io::Result<usize> {
+            match &self.target {
+                Target::Terminal => self.play(sim, &mut io::stdout()),
+                Target::Directory(dir) => self.write_frames(sim, dir),
+            }
+        }
+
+        pub fn play<S: Simulation>(&self, sim: &mut S, out: &mut impl Write) -> io::Result<usize> {
+            let mut count = 0;
+            for frame in Frames::new(sim, self.max_frames) {
+                if count > 0 {
+                    thread::sleep(self.delay);
+                }
+                write!(out, "{}{}", CLEAR_SCREEN, frame)?;
+                out.flush()?;
+                count += 1;
+            }
+            Ok(count)
+        }
+
+        // Writes frame-00000.txt, frame-00001.txt, etc. or .ppm files if a palette was provided
+        pub fn write_frames<S: Simulation>(&self, sim: &mut S, dir: impl AsRef<Path>) -> io::Result<usize> {
+            let dir = dir.as_ref();
+            fs::create_dir_all(dir)?;
+            let mut count = 0;
+            for frame in Frames::new(sim, self.max_frames) {
+                match &self.palette {
+                    Some((palette, scale)) => {
+                        let path = dir.join(format!("frame-{:05}.ppm", count));
+                        Image::from_text(&frame, palette).scale(*scale).save(path)?;
+                    },
+                    None => fs::write(dir.join(format!("frame-{:05}.txt", count)), frame)?,
+                }
+                count += 1;
+            }
+            Ok(count)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use super::super::tests::Counter;
+        use crate::image::Color;
+
+        fn args(args: &[&str]) -> Vec<String> {
+            args.iter().map(|s| s.to_string()).collect()
+        }
+
+        #[test]
+        fn parse_args() {
+            assert!(Exporter::from_args(&args(&["--render", "foo.ppm"])).is_none());
+
+            let exporter = Exporter::from_args(&args(&["--animate", "--delay", "5"])).unwrap();
+            assert_eq!(exporter.target, Target::Terminal);
+            assert_eq!(exporter.delay, Duration::from_millis(5));
+
+            let exporter = Exporter::from_args(&args(&["--frames", "out", "--max-frames", "3"])).unwrap();
+            assert_eq!(exporter.target, Target::Directory("out".into()));
+            assert_eq!(exporter.max_frames, 3);
+        }
+
+        #[test]
+        fn play() {
+            let mut out = Vec::new();
+            let exporter = Exporter::new(Target::Terminal).delay(Duration::from_millis(0));
+            assert_eq!(exporter.play(&mut Counter::new(2), &mut out).unwrap(), 3);
+            let out = String::from_utf8(out).unwrap();
+            assert_eq!(out, format!("{0}0{0}1{0}2", CLEAR_SCREEN));
+        }
+
+        #[test]
+        fn write_frames() {
+            let dir = std::env::temp_dir().join(format!("advent-frames-{}", std::process::id()));
+            let exporter = Exporter::new(Target::Directory(dir.clone())).max_frames(2);
+            assert_eq!(exporter.run(&mut Counter::new(5)).unwrap(), 2);
+            assert_eq!(fs::read_to_string(dir.join("frame-00001.txt")).unwrap(), "1");
+
+            let exporter = exporter.palette(Palette::new(Color::BLACK), 2);
+            assert_eq!(exporter.run(&mut Counter::new(0)).unwrap(), 1);
+            let image = fs::read(dir.join("frame-00000.ppm")).unwrap();
+            assert!(image.starts_with(b"P6\n2 2\n"));
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+}
+pub use self::exporter::Exporter;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counts up to the given limit
+    pub struct Counter {
+        count: u32,
+        limit: u32,
+    }
+
+    impl Counter {
+        pub fn new(limit: u32) -> Counter {
+            Counter { count: 0, limit }
+        }
+    }
+
+    impl Simulation for Counter {
+        fn step(&mut self) -> bool {
+            if self.count == self.limit {
+                return false;
+            }
+            self.count += 1;
+            true
+        }
+
+        fn render(&self) -> String {
+            self.count.to_string()
+        }
+    }
+
+    #[test]
+    fn frames() {
+        let mut counter = Counter::new(3);
+        assert_eq!(Frames::new(&mut counter, 10).collect::<Vec<_>>(), vec!("0", "1", "2", "3"));
+        assert_eq!(counter.count, 3);
+
+        let mut counter = Counter::new(3);
+        assert_eq!(Frames::new(&mut counter, 2).collect::<Vec<_>>(), vec!("0", "1"));
+        assert_eq!(Frames::new(&mut counter, 0).count(), 0);
+    }
+}
@@ -1,17 +1,31 @@
 use std::collections::HashSet;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use crate::animation::{Exporter, Simulation};
 use crate::euclid::{point,Point,Vector};
+use crate::image::{self, Color, Image, Palette};
 
 const REAL_DATA: &str = "data/day10.txt";
 #[cfg(test)]
 const TEST_DATA: &str = "data/day10-example.txt";
 
-pub fn advent() {
+pub fn advent(args: &[String]) {
+    if let Some(exporter) = Exporter::from_args(args) {
+        let palette = Palette::new(Color::BLACK).color('#', Color::WHITE);
+        exporter.palette(palette, 4).run(&mut Sky::new(read_data(REAL_DATA))).expect("Cannot animate");
+        return;
+    }
+
     let stars = read_data(REAL_DATA);
     let (steps, message) = find_message(stars);
     println!("Message:\n{}", message);
     println!("After {} seconds", steps);
+
+    if let Some(path) = image::render_path(args) {
+        let palette = Palette::new(Color::BLACK).color('#', Color::WHITE);
+        Image::from_text(&message, &palette).scale(8).save(path).expect("Cannot render");
+        println!("Rendered to {}", path);
+    }
 }
 
 fn read_data(path: &str) -> Vec<Star> {
@@ -50,24 +64,68 @@ fn stars_to_string(bounds: (Point, Point), stars: &Vec<Star>) -> String {
     out
 }
 
-// Not sure if there's a good way to pass in an &Vec<Star> instead
-fn find_message(stars: Vec<Star>) -> (u32, String) {
-    let mut stars = stars;
-    let mut bounds = Point::bounding_box(stars.iter().map(|s| s.position)).unwrap();
-    let mut steps = 0;
-    loop {
-        let next_stars: Vec<_> = stars.iter().map(Star::step).collect();
+// Early on the stars are spread across billions of cells, so scale them down to fit in a terminal
+fn stars_to_thumbnail(bounds: (Point, Point), stars: &[Star]) -> String {
+    const COLUMNS: i64 = 100;
+    const ROWS: i64 = 50;
+    let width = (bounds.1.x - bounds.0.x) as i64 + 1;
+    let height = (bounds.1.y - bounds.0.y) as i64 + 1;
+    let scale = ((width + COLUMNS - 1) / COLUMNS).max((height + ROWS - 1) / ROWS);
+    let cell = |p: Point| (((p.x - bounds.0.x) as i64 / scale) as usize, ((p.y - bounds.0.y) as i64 / scale) as usize);
+
+    let (columns, rows) = cell(bounds.1);
+    let mut cells = vec![vec!['.'; columns + 1]; rows + 1];
+    for star in stars {
+        let (x, y) = cell(star.position);
+        cells[y][x] = '#';
+    }
+    cells.into_iter().map(|row| row.into_iter().chain(std::iter::once('\n')).collect::<String>()).collect()
+}
+
+// The stars converge on a message and then drift apart again. The simulation ends on the message,
+// i.e. when the stars' bounding box is smallest.
+struct Sky {
+    stars: Vec<Star>,
+    bounds: (Point, Point),
+    seconds: u32,
+}
+
+impl Sky {
+    fn new(stars: Vec<Star>) -> Sky {
+        let bounds = bounding_box(&stars);
+        Sky { stars, bounds, seconds: 0 }
+    }
+}
+
+impl Simulation for Sky {
+    fn step(&mut self) -> bool {
+        let next_stars: Vec<_> = self.stars.iter().map(Star::step).collect();
         let next_bounds = bounding_box(&next_stars);
         // If the star field starts expanding
-        if area(next_bounds.0, next_bounds.1) > area(bounds.0, bounds.1) {
-            return (steps, stars_to_string(bounds, &stars));
+        if area(next_bounds.0, next_bounds.1) > area(self.bounds.0, self.bounds.1) {
+            return false;
+        }
+        self.stars = next_stars;
+        self.bounds = next_bounds;
+        self.seconds += 1;
+        true
+    }
+
+    fn render(&self) -> String {
+        if area(self.bounds.0, self.bounds.1) < 10000 {
+            stars_to_string(self.bounds, &self.stars)
+        } else {
+            stars_to_thumbnail(self.bounds, &self.stars)
         }
-        stars = next_stars;
-        bounds = next_bounds;
-        steps += 1;
     }
 }
 
+fn find_message(stars: Vec<Star>) -> (u32, String) {
+    let mut sky = Sky::new(stars);
+    while sky.step() {}
+    (sky.seconds, sky.render())
+}
+
 mod star {
     use super::{Point, Vector};
     use regex::Regex;
@@ -175,4 +233,18 @@ mod tests {
 
         assert_eq!(find_message(read_data(TEST_DATA)), (3, message.into()));
     }
+
+    #[test]
+    fn thumbnail() {
+        let stars: Vec<_> = vec!(point(0, 0), point(150, 10), point(399, 99)).into_iter()
+            .map(|p| Star::new(p, vector(0, 0))).collect();
+        // scaled down by 4
+        let thumbnail = stars_to_thumbnail(bounding_box(&stars), &stars);
+        let rows: Vec<_> = thumbnail.lines().collect();
+        assert_eq!((rows[0].len(), rows.len()), (100, 25));
+        assert_eq!(rows[0].find('#'), Some(0));
+        assert_eq!(rows[2].find('#'), Some(37));
+        assert_eq!(rows[24].find('#'), Some(99));
+        assert_eq!(thumbnail.matches('#').count(), 3);
+    }
 }
@@ -1,9 +1,16 @@
-use std::collections::HashSet;
 use regex::Regex;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use crate::animation::Exporter;
+use crate::image::{Color, Palette};
+
+pub fn advent(args: &[String]) {
+    if let Some(exporter) = Exporter::from_args(args) {
+        let palette = Palette::new(Color::BLACK).color('#', Color::rgb(0, 200, 0));
+        exporter.palette(palette, 4).run(&mut read_data()).expect("Cannot animate");
+        return;
+    }
 
-pub fn advent() {
     let mut pots = read_data();
     pots.advance(20);
     println!("{:?}", pots);
@@ -13,7 +20,6 @@ pub fn advent() {
 
 lazy_static! {
     static ref STATE_RE: Regex = Regex::new(r"^initial state: ([#.]+)$").unwrap();
-    static ref RULE_RE: Regex = Regex::new(r"^([#.]{5}) => ([#.])$").unwrap();
 }
 
 fn read_data() -> Pots {
@@ -24,157 +30,65 @@ fn read_data() -> Pots {
     Pots::new(initial_state, &lines[2..])
 }
 
-fn to_bool(c: char) -> bool {
-    match c {
-        '#' => true,
-        '.' => false,
-        _ => panic!("invalid char: {:?}", c),
-    }
-}
-
-fn window(s: &str) -> [bool;5] {
-    assert_eq!(s.len(), 5);
-
-    // https://stackoverflow.com/a/29570662/113632
-    let mut array = [false; 5];
-    let v: Vec<bool> = s.chars().map(to_bool).collect();
-    array.copy_from_slice(&v);
-    array
-}
-
-fn rules(lines: &[String]) -> HashSet<[bool;5]> {
-    let mut set = HashSet::new();
-    for line in lines {
-        let caps = RULE_RE.captures(&line).expect("must match");
-        let window = window(capture_group!(caps, 1));
-        let is_alive = capture_group!(caps, 2);
-        if to_bool(is_alive.chars().next().unwrap()) {
-            set.insert(window);
-        }
-    }
-    assert!(!set.contains(&[false;5]), "Cannot create life from nothing");
-    set
-}
-
 mod pots {
-    use super::to_bool;
     use std::cmp;
-    use std::collections::HashSet;
     use std::fmt;
+    use crate::animation::Simulation;
+    use crate::automaton::{self, Automaton1D, Rule1D};
 
     pub struct Pots {
-        stable_shift: Option<i64>,
-        state: Vec<bool>,
-        rules: HashSet<[bool;5]>,
-        offset: i64,
-        generation: u64,
+        automaton: Automaton1D,
     }
 
     impl Pots {
         pub fn new(initial: &str, rules: &[String]) -> Pots {
-            let state = initial.chars().map(to_bool).collect();
-            let rules = super::rules(rules);
-            Pots { stable_shift: None, state, rules, offset: 0, generation: 0 }
-        }
-
-        fn shift_offsets(&mut self) {
-            let first_true = self.state.iter().position(|&x| x == true)
-                .expect("Expected a true element");
-            let shift_by = 4 - cmp::min(4, first_true); // no need to shift by more than 4
-            if shift_by > 0 {
-                let mut new_state = Vec::new();
-                new_state.resize(shift_by, false);
-                new_state.extend_from_slice(&self.state);
-                self.state = new_state;
-                self.offset += shift_by as i64;
-            }
-
-            let last_true = self.state.iter().rposition(|&x| x == true)
-                .expect("Expected a true element");
-            let extend_to = cmp::max(self.state.len(), last_true + 4);
-            self.state.resize(extend_to, false);
-        }
-
-        fn check_stability(&mut self, new_state: &Vec<bool>) {
-            assert!(self.stable_shift.is_none());
-            let bounds = |v: &Vec<bool>| (
-                v.iter().position(|&x| x == true).unwrap(),
-                v.iter().rposition(|&x| x == true).unwrap());
-
-            let cur_bounds = bounds(&self.state);
-            let new_bounds = bounds(new_state);
-
-            if self.state[cur_bounds.0..cur_bounds.1] == new_state[new_bounds.0..new_bounds.1] {
-                self.stable_shift = Some(new_bounds.0 as i64 - cur_bounds.0 as i64);
-            }
-        }
-
-        fn advance_impl(&mut self) {
-            assert!(self.stable_shift.is_none());
-            self.shift_offsets();
-
-            let mut new_state = Vec::new();
-            new_state.resize(self.state.len(), false);
-
-            // We can ignore the first two and last two pots, becuase shift_offsets ensures they're
-            // [FFFF*]/[*FFFF] which means (since further pots are also false) the first two and
-            // last two pots cannot germinate in this generation. Note that `..... => #` is banned.
-            for i in 2..self.state.len() - 2 {
-                let mut array = [false; 5];
-                array.copy_from_slice(&self.state[i - 2..i + 3]);
-                if self.rules.contains(&array) {
-                    new_state[i] = true;
-                }
-            }
-
-            self.check_stability(&new_state);
-
-            self.state = new_state;
+            let state = automaton::parse_states(initial).expect("Invalid state");
+            let rule = Rule1D::parse(rules).expect("Invalid rules");
+            assert_eq!((rule.radius(), rule.states()), (2, 2), "Expected five-pot rules");
+            Pots { automaton: Automaton1D::new(&state, rule) }
         }
 
         pub fn advance(&mut self, generations: u64) {
-            let mut i = generations;
-            while i > 0 && self.stable_shift.is_none() {
-                self.advance_impl();
-                i -= 1;
-            }
-            self.offset -= self.stable_shift.unwrap_or(0) * i as i64;
-            self.generation += generations;
+            self.automaton.advance(generations);
         }
 
         pub fn score(&self) -> i64 {
-            let mut sum = 0;
-            for i in 0..self.state.len() {
-                if self.state[i] {
-                    sum += i as i64 - self.offset;
-                }
-            }
-            sum
+            self.automaton.live_cells().map(|(position, _)| position).sum()
         }
 
         pub fn generation(&self) -> u64 {
-            self.generation
+            self.automaton.generation()
         }
 
         pub fn is_stable(&self) -> bool {
-            self.stable_shift.is_some()
+            self.automaton.is_stable()
+        }
+    }
+
+    // Renders one generation per frame; there's no natural end, so callers should cap the frames
+    impl Simulation for Pots {
+        fn step(&mut self) -> bool {
+            self.advance(1);
+            true
+        }
+
+        fn render(&self) -> String {
+            self.to_string()
         }
     }
 
     impl fmt::Display for Pots {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            let state = self.state.iter().map(|&x| if x { "#" } else { "." })
-                .collect::<String>();
-            let offset = std::iter::repeat(".").take(cmp::max(0, 0-self.offset) as usize)
+            let state = self.automaton.cells().iter().map(|&x| automaton::symbol(x))
                 .collect::<String>();
-            write!(f, "{:3}: {}{}", self.generation, offset, state)
+            let offset = ".".repeat(cmp::max(0, 0-self.automaton.offset()) as usize);
+            write!(f, "{:3}: {}{}", self.generation(), offset, state)
         }
     }
 
     impl fmt::Debug for Pots {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            let offset_mark = std::iter::repeat(" ").take(cmp::max(0, self.offset) as usize)
-                .collect::<String>();
+            let offset_mark = " ".repeat(cmp::max(0, self.automaton.offset()) as usize);
             write!(f, "{}\n     {}^  Score:{}{}",
                    self, offset_mark, self.score(), if self.is_stable() { " STABLE" } else { "" })
         }
@@ -195,13 +109,13 @@ mod pots {
 
             pots.advance(1);
             assert_eq!(format!("{:?}", pots),
-                       "  1: ....#...........................\n         ^  Score:0");
+                       "  1: ....#............................\n         ^  Score:0");
             assert!(!pots.is_stable()); // first stable generation isn't stable *yet*
 
             pots.advance(1);
             assert!(pots.is_stable());
             assert_eq!(format!("{:?}", pots),
-                       "  2: ....#...........................\n         ^  Score:0 STABLE");
+                       "  2: ....#............................\n         ^  Score:0 STABLE");
         }
     }
 }
@@ -215,11 +129,6 @@ mod tests {
         assert_eq!(read_data().score(), 2434);
     }
 
-    #[test]
-    fn windows() {
-        assert_eq!(window("#.#.#"), [true, false, true, false, true]);
-    }
-
     fn example_rules() -> Vec<String> {
         vec!(
             "...## => #",
@@ -241,8 +150,10 @@ mod tests {
 
     #[test]
     fn check_example() {
-        let rules = rules(&example_rules());
-        assert_eq!(rules.len(), 14);
+        let rule = crate::automaton::Rule1D::parse(&example_rules()).unwrap();
+        assert_eq!((rule.radius(), rule.states()), (2, 2));
+        assert_eq!(rule.next(&[0, 0, 0, 1, 1]), 1);
+        assert_eq!(rule.next(&[1, 1, 1, 1, 1]), 0);
     }
 
     #[test]
@@ -257,4 +168,14 @@ mod tests {
         pots.advance(1);
         assert!(pots.is_stable());
     }
+
+    #[test]
+    fn frames() {
+        let mut pots = Pots::new("#..#.#..##......###...###", &example_rules());
+        let frames: Vec<_> = crate::animation::Frames::new(&mut pots, 21).collect();
+        assert_eq!(frames[0], "  0: #..#.#..##......###...###");
+        assert!(frames[20].starts_with(" 20: "));
+        assert_eq!(pots.generation(), 20);
+        assert_eq!(pots.score(), 325);
+    }
 }
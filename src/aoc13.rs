@@ -1,7 +1,19 @@
 use std::fs;
-use crate::euclid::{vector, Vector};
+use crate::animation::Exporter;
+use crate::euclid::Direction;
+use crate::image::{Color, Palette};
+
+pub fn advent(args: &[String]) {
+    if let Some(exporter) = Exporter::from_args(args) {
+        let palette = Palette::new(Color::BLACK)
+            .color('|', Color::gray(128)).color('-', Color::gray(128)).color('/', Color::gray(128))
+            .color('\\', Color::gray(128)).color('+', Color::gray(192))
+            .color('^', Color::rgb(255, 0, 0)).color('v', Color::rgb(255, 0, 0))
+            .color('<', Color::rgb(255, 0, 0)).color('>', Color::rgb(255, 0, 0));
+        exporter.palette(palette, 4).run(&mut read_data()).expect("Cannot animate");
+        return;
+    }
 
-pub fn advent() {
     let mut tracks = read_data();
     loop {
         if let Some(coord) = tracks.advance() {
@@ -18,31 +30,13 @@ fn read_data() -> Tracks {
         .parse().expect("invalid file")
 }
 
-#[derive(Clone, Copy, Eq, Debug, PartialEq)]
-pub enum Direction {
-    North,
-    South,
-    East,
-    West,
-}
-
-impl Direction {
-    fn as_vector(self) -> Vector {
-        match self {
-            Direction::North => vector(0, -1),
-            Direction::South => vector(0, 1),
-            Direction::East => vector(1, 0),
-            Direction::West => vector(-1, 0),
-        }
-    }
-}
-
 mod track {
     use std::collections::HashMap;
     use std::fmt;
     use std::fmt::Write;
     use std::str::FromStr;
-    use crate::euclid::{point, Point};
+    use crate::animation::Simulation;
+    use crate::euclid::{point, AsciiMap, Point};
     use super::cart::Cart;
     use super::Direction;
 
@@ -102,35 +96,41 @@ mod track {
         }
     }
 
+    // Runs until at most one cart remains
+    impl Simulation for Tracks {
+        fn step(&mut self) -> bool {
+            if self.carts.len() < 2 {
+                return false;
+            }
+            self.advance();
+            true
+        }
+
+        fn render(&self) -> String {
+            self.to_string()
+        }
+    }
+
     impl FromStr for Tracks {
         type Err = String;
 
         fn from_str(s: &str) -> Result<Self, String> {
-            let mut tracks = HashMap::new();
-            let mut carts = HashMap::new();
-            let mut coord = point(0, 0);
-
-            for c in s.chars() {
-                let c = match c {
-                    '<' => { carts.insert(coord, Cart::new(Direction::West)); '-'},
-                    '>' => { carts.insert(coord, Cart::new(Direction::East)); '-'},
-                    '^' => { carts.insert(coord, Cart::new(Direction::North)); '|'},
-                    'v' => { carts.insert(coord, Cart::new(Direction::South)); '|'},
-                    c => c,
-                };
-                match c {
-                    '|' => { tracks.insert(coord, TrackType::Vertical); },
-                    '-' => { tracks.insert(coord, TrackType::Horizontal); },
-                    '/' => { tracks.insert(coord, TrackType::WestNorth_EastSouth); },
-                    '\\' => { tracks.insert(coord, TrackType::WestSouth_EastNorth); },
-                    '+' => { tracks.insert(coord, TrackType::Intersection); },
-                    ' ' => {},
-                    '\n' => { coord = point(-1, coord.y + 1); },
-                    _ => { return Err(format!("Unexpected char {} at {}", c, coord)); },
-                }
-                coord = point(coord.x + 1, coord.y);
-            }
-
+            let (tracks, carts) = AsciiMap::new()
+                .terrain(' ', None)
+                .terrain('|', Some(TrackType::Vertical))
+                .terrain('-', Some(TrackType::Horizontal))
+                .terrain('/', Some(TrackType::WestNorth_EastSouth))
+                .terrain('\\', Some(TrackType::WestSouth_EastNorth))
+                .terrain('+', Some(TrackType::Intersection))
+                .entity('^', Direction::North, Some(TrackType::Vertical))
+                .entity('v', Direction::South, Some(TrackType::Vertical))
+                .entity('>', Direction::East, Some(TrackType::Horizontal))
+                .entity('<', Direction::West, Some(TrackType::Horizontal))
+                .padding(None)
+                .parse(s).map_err(|e| e.to_string())?;
+
+            let tracks = tracks.iter().filter_map(|(p, t)| t.map(|t| (p, t))).collect();
+            let carts = carts.into_iter().map(|(p, d)| (p, Cart::new(d))).collect();
             Ok(Tracks { tracks, carts })
         }
     }
@@ -220,6 +220,13 @@ mod track {
             assert_eq!(tracks.advance(), Some(point(0, 1)));
             assert_eq!(tracks.to_string(), "/---\\\n\\---/\n");
         }
+
+        #[test]
+        fn simulate() {
+            let mut tracks: Tracks = "->---<-\n".parse().unwrap();
+            let frames: Vec<_> = crate::animation::Frames::new(&mut tracks, 10).collect();
+            assert_eq!(frames, vec!("->---<-\n", "-->-<--\n", "-------\n"));
+        }
     }
 }
 pub use self::track::Tracks;
@@ -285,31 +292,17 @@ mod cart {
         }
 
         fn turn(direction: Direction, turn: TurnState) -> Direction {
-            match (direction, turn) {
-                (Direction::North, TurnState::Left) => Direction::West,
-                (Direction::West, TurnState::Left) => Direction::South,
-                (Direction::South, TurnState::Left) => Direction::East,
-                (Direction::East, TurnState::Left) => Direction::North,
-
-                (Direction::North, TurnState::Right) => Direction::East,
-                (Direction::West, TurnState::Right) => Direction::North,
-                (Direction::South, TurnState::Right) => Direction::West,
-                (Direction::East, TurnState::Right) => Direction::South,
-
-                (d, TurnState::Straight) => d,
+            match turn {
+                TurnState::Left => direction.left(),
+                TurnState::Straight => direction,
+                TurnState::Right => direction.right(),
             }
         }
     }
 
     impl fmt::Display for Cart {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            let c = match self.direction {
-                Direction::North => '^',
-                Direction::South => 'v',
-                Direction::East => '>',
-                Direction::West => '<',
-            };
-            write!(f, "{}", c)
+            write!(f, "{}", self.direction.arrow())
         }
     }
 
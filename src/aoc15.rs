@@ -1,16 +1,39 @@
 use std::fs;
+use crate::animation::Exporter;
+use crate::image::{self, Color, Image, Palette};
 
-pub fn advent() {
+pub fn advent(args: &[String]) {
     let input = read_data();
+    if let Some(exporter) = Exporter::from_args(args) {
+        let mut cave: Cave = input.parse().expect("Invalid file");
+        exporter.palette(palette(), 8).run(&mut cave).expect("Cannot animate");
+        return;
+    }
+
     let mut cave: Cave = input.parse().expect("Invalid file");
     let (winner, rounds, _, outcome) = cave.combat();
     println!("Victor: {:?} - after {} rounds outcome: {}", winner, rounds, outcome);
+    if let Some(path) = image::render_path(args) {
+        render(&cave).save(path).expect("Cannot render");
+        println!("Rendered to {}", path);
+    }
 
     let (attack_power, winner, rounds, _, outcome) = increase_attack_power(&input);
     println!("With {} attack power, victor: {:?} - after {} rounds outcome: {}",
              attack_power, winner, rounds, outcome);
 }
 
+fn palette() -> Palette<char> {
+    Palette::new(Color::gray(64)) // walls
+        .color('.', Color::rgb(222, 184, 135))
+        .color('E', Color::rgb(0, 160, 0))
+        .color('G', Color::rgb(200, 0, 0))
+}
+
+fn render(cave: &Cave) -> Image {
+    Image::from_text(&cave.to_string(), &palette()).scale(8)
+}
+
 fn read_data() -> String {
     fs::read_to_string("data/day15.txt").expect("Cannot open")
 }
@@ -37,12 +60,10 @@ mod cave {
     use std::fmt;
     use std::fmt::Write;
     use std::str::FromStr;
-    use crate::euclid::{point, Point, vector, Vector};
+    use crate::animation::Simulation;
+    use crate::euclid::{point, AsciiMap, Point};
     use super::*;
 
-    static DIRECTIONS: [Vector; 4] =
-        [vector(0, -1), vector(-1, 0), vector(1, 0), vector(0, 1)];
-
     pub struct Cave {
         squares: HashSet<Point>,
         units: HashMap<Point, Unit>,
@@ -125,7 +146,7 @@ mod cave {
             let unit = self.units.get(&coord).expect(&format!("No unit found at {}", coord));
             let unit_race = *unit.race();
             let unit_attack_power = unit.attack_power();
-            let target = DIRECTIONS.iter().map(|v| coord + v)
+            let target = coord.neighbors4()
                 .filter(|p| self.squares.contains(p))
                 .flat_map(|p| {
                     let p = p.clone();
@@ -161,8 +182,7 @@ mod cave {
             let mut queued: Vec<(Point, Point)> = Vec::new();
 
             // Inspect neighbors and populate queued
-            // TODO pull DIRECTIONS.iter().map(|v| coord + v) into a helper?
-            for neighbor in DIRECTIONS.iter().map(|v| coord + v) {
+            for neighbor in coord.neighbors4() {
                 seen.insert(neighbor);
                 if self.squares.contains(&neighbor) {
                     if let Some(u) = self.units.get(&neighbor) {
@@ -175,7 +195,7 @@ mod cave {
                         }
                     }
 
-                    for next_neighbor in DIRECTIONS.iter().map(|v| neighbor + v) {
+                    for next_neighbor in neighbor.neighbors4() {
                         if !seen.contains(&next_neighbor) {
                             queued.push((next_neighbor, neighbor));
                         }
@@ -205,7 +225,7 @@ mod cave {
                             }
                         }
 
-                        for next_dest in DIRECTIONS.iter().map(|v| dest + v) {
+                        for next_dest in dest.neighbors4() {
                             if !seen.contains(&next_dest) {
                                 queued.push((next_dest, first_step));
                             }
@@ -222,25 +242,16 @@ mod cave {
         type Err = String;
 
         fn from_str(s: &str) -> Result<Self, String> {
-            let mut squares =  HashSet::new();
-            let mut units = HashMap::new();
-            let mut coord = point(0, 0);
-
-            for c in s.chars() {
-                let c = match c {
-                    'E' => { units.insert(coord, Unit::new(Race::Elf)); '.' },
-                    'G' => { units.insert(coord, Unit::new(Race::Goblin)); '.' },
-                    c => c,
-                };
-                match c {
-                    '.' => { squares.insert(coord); },
-                    '#' => {},
-                    '\n' => { coord = point(-1, coord.y + 1); },
-                    _ => { return Err(format!("Unexpected char {} at {}", c, coord)); },
-                };
-                coord = point(coord.x + 1, coord.y);
-            }
-
+            let (terrain, units) = AsciiMap::new()
+                .terrain('#', false)
+                .terrain('.', true)
+                .entity('E', Race::Elf, true)
+                .entity('G', Race::Goblin, true)
+                .padding(false) // treat ragged lines as though they end in walls
+                .parse(s).map_err(|e| e.to_string())?;
+
+            let squares = terrain.iter().filter(|&(_, &open)| open).map(|(p, _)| p).collect();
+            let units = units.into_iter().map(|(p, race)| (p, Unit::new(race))).collect();
             Ok(Cave { squares, units, killed: HashSet::new(), rounds: 0 })
         }
     }
@@ -273,6 +284,21 @@ mod cave {
         }
     }
 
+    // Each step is one round of combat, until only one race remains
+    impl Simulation for Cave {
+        fn step(&mut self) -> bool {
+            if !self.continue_combat() {
+                return false;
+            }
+            self.move_all_units();
+            true
+        }
+
+        fn render(&self) -> String {
+            self.to_string()
+        }
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -589,6 +615,19 @@ mod tests {
     #[test]
     fn read_file() { read_data().parse::<Cave>().unwrap(); }
 
+    #[test]
+    fn frames() {
+        let input = "#######\n#.G...#\n#...EG#\n#.#.#G#\n#..G#E#\n#.....#\n#######";
+        let mut cave: Cave = input.parse().unwrap();
+        let frames: Vec<_> = crate::animation::Frames::new(&mut cave, 100).collect();
+        // The initial state, plus one frame per round
+        assert_eq!(frames.len(), 48);
+
+        let mut expected: Cave = input.parse().unwrap();
+        expected.combat();
+        assert_eq!(frames.last(), Some(&expected.to_string()));
+    }
+
     parameterized_test::create!{ attack_power, (input, attack_power, rounds, health), {
         let outcome = increase_attack_power(input);
         assert_eq!(outcome, (attack_power, Race::Elf, rounds, health, rounds*health));
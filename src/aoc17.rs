@@ -1,11 +1,36 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use crate::animation::Exporter;
+use crate::image::{self, Color, Image, Palette};
 
-pub fn advent() {
+pub fn advent(args: &[String]) {
     let scan = read_data();
+    if let Some(exporter) = Exporter::from_args(args) {
+        exporter.palette(palette(), 1).run(&mut Flow::start(&scan)).expect("Cannot animate");
+        return;
+    }
+
     let flow = Flow::new(&scan);
     println!("Reachable tiles: {}", flow.reachable());
     println!("Retained water:  {}", flow.retained());
+
+    // The scan is thousands of rows tall, far too large to print
+    if let Some(path) = image::render_path(args) {
+        render(&flow).save(path).expect("Cannot render");
+        println!("Rendered to {}", path);
+    }
+}
+
+fn palette() -> Palette<char> {
+    Palette::new(Color::rgb(237, 201, 175)) // sand
+        .color('+', Color::rgb(255, 0, 0))
+        .color('~', Color::rgb(0, 0, 205))
+        .color('|', Color::rgb(135, 206, 250))
+        .color('#', Color::rgb(101, 67, 33))
+}
+
+fn render(flow: &Flow) -> Image {
+    Image::from_text(&flow.to_string(), &palette())
 }
 
 // TODO unit test reading this? Maybe too slow to actually construct the Scan
@@ -172,6 +197,7 @@ pub use scan::Scan;
 mod flow {
     use std::collections::HashSet;
     use std::fmt;
+    use crate::animation::Simulation;
     use crate::euclid::{Point, vector, Vector};
     use super::*;
 
@@ -183,26 +209,18 @@ mod flow {
 
     impl<'a> Flow<'a> {
         pub fn new(scan: &Scan) -> Flow {
-            let mut flow = Flow {
+            let mut flow = Flow::start(scan);
+            while flow.step() {}
+            flow
+        }
+
+        // No water has flowed yet; step() to advance the water one tile at a time
+        pub fn start(scan: &Scan) -> Flow<'_> {
+            Flow {
                 scan,
                 rest: HashSet::new(),
                 flow: HashSet::new(),
-            };
-
-            let mut last_rest = flow.rest.len();
-            let mut last_flow = flow.flow.len();
-            loop {
-                flow.advance();
-                let cur_rest = flow.rest.len();
-                let cur_flow = flow.flow.len();
-                if last_rest == cur_rest && last_flow == cur_flow {
-                    break;
-                }
-                last_rest = cur_rest;
-                last_flow = cur_flow;
             }
-
-            flow
         }
 
         fn advance(&mut self) {
@@ -302,6 +320,19 @@ mod flow {
     }
 
 
+    // Finishes once the water stops spreading
+    impl<'a> Simulation for Flow<'a> {
+        fn step(&mut self) -> bool {
+            let last = (self.rest.len(), self.flow.len());
+            self.advance();
+            last != (self.rest.len(), self.flow.len())
+        }
+
+        fn render(&self) -> String {
+            self.to_string()
+        }
+    }
+
     impl<'a> fmt::Display for Flow<'a> {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
             self.scan.display_helper(f, &self.rest, &self.flow)
@@ -321,6 +352,16 @@ mod flow {
             assert_eq!(flow.reachable(), 8); // overflow is out of bounds?
             assert_eq!(flow.retained(), 4);
             assert_eq!(flow.to_string(), "....+...\n||||||||\n|#~~~~#|\n|######|\n........\n");
+
+            let image = render(&flow);
+            assert_eq!((image.width(), image.height()), (8, 5));
+            assert_eq!(image.get(4, 0), Color::rgb(255, 0, 0));
+            assert_eq!(image.get(2, 2), Color::rgb(0, 0, 205));
+
+            let mut stepped = Flow::start(&scan);
+            assert_eq!(stepped.render(), scan.to_string());
+            let frames: Vec<_> = crate::animation::Frames::new(&mut stepped, 100).collect();
+            assert_eq!(frames.last(), Some(&flow.to_string()));
         }
 
         #[test]
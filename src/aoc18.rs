@@ -1,32 +1,114 @@
 use std::fs;
+use std::time::{Duration, Instant};
+use crate::animation::{Exporter, Simulation};
+use crate::automaton::Rules2D;
+use crate::image::{self, Color, Image, Palette};
 
 const REAL_DATA: &str = "data/day18.txt";
 #[cfg(test)]
 const TEST_DATA: &str = "data/day18-example.txt";
 
-pub fn advent() {
-    let mut landscape = read_data(REAL_DATA);
+pub fn advent(args: &[String]) {
+    if args.iter().any(|a| a == "--bench") {
+        benchmark();
+        return;
+    }
+
+    // Only the general Landscape supports custom rules, otherwise use the faster PackedLandscape
+    match args.iter().position(|a| a == "--rules") {
+        Some(i) => {
+            let path = args.get(i+1).expect("--rules requires a FILE");
+            let rules = fs::read_to_string(path).expect("Cannot open").parse()
+                .unwrap_or_else(|e| panic!("Invalid rules: {}", e));
+            solve(read_data_with_rules(REAL_DATA, rules), args);
+        },
+        None => solve(read_packed(REAL_DATA), args),
+    }
+}
+
+fn solve<A: Acres + Simulation>(mut landscape: A, args: &[String]) {
+    if let Some(exporter) = Exporter::from_args(args) {
+        exporter.palette(palette(), 4).run(&mut landscape).expect("Cannot animate");
+        return;
+    }
+
     landscape.tick(10);
-    let counts = landscape.counts();
-    println!("Resources after {} minutes:\t{}",
-             landscape.minute(), counts.get(&State::TREES).unwrap() * counts.get(&State::YARD).unwrap());
+    println!("Resources after {} minutes:\t{}", landscape.minute(), resource_value(&landscape));
     let cycle_size = landscape.find_cycle_size();
     println!("Found cycle after: {} minutes - Cycle Size: {}", landscape.minute(), cycle_size);
     landscape.tick_to(1000000000);
+    println!("Resources after {} minutes:\t{}", landscape.minute(), resource_value(&landscape));
+
+    if let Some(path) = image::render_path(args) {
+        render(&landscape).save(path).expect("Cannot render");
+        println!("Rendered to {}", path);
+    }
+}
+
+fn resource_value(landscape: &impl Acres) -> usize {
     let counts = landscape.counts();
-    println!("Resources after {} minutes:\t{}",
-             landscape.minute(), counts.get(&State::TREES).unwrap() * counts.get(&State::YARD).unwrap());
+    counts.get(&State::TREES).unwrap_or(&0) * counts.get(&State::YARD).unwrap_or(&0)
+}
+
+// Compares the backends, both ticking minute-by-minute and skipping ahead to one billion minutes
+// once the cycle is found. Run with --release for meaningful numbers.
+fn benchmark() {
+    if cfg!(debug_assertions) {
+        println!("WARNING: without --release these timings aren't representative");
+    }
+    println!("{:>10} {:>16} {:>16}", "Backend", "1000 minutes", "1e9 minutes");
+    let (ticks, total) = time_backend(|| read_data(REAL_DATA));
+    println!("{:>10} {:>16?} {:>16?}", "Landscape", ticks, total);
+    let (packed_ticks, packed_total) = time_backend(|| read_packed(REAL_DATA));
+    println!("{:>10} {:>16?} {:>16?}", "Packed", packed_ticks, packed_total);
+    println!("{:>10} {:>15.1}x {:>15.1}x", "Speedup",
+             ticks.as_secs_f64() / packed_ticks.as_secs_f64(), total.as_secs_f64() / packed_total.as_secs_f64());
+}
+
+fn time_backend<A: Acres>(read: impl Fn() -> A) -> (Duration, Duration) {
+    let mut landscape = read();
+    let start = Instant::now();
+    landscape.tick(1000);
+    let ticks = start.elapsed();
+
+    let mut landscape = read();
+    let start = Instant::now();
+    landscape.find_period();
+    landscape.tick_to(1000000000);
+    (ticks, start.elapsed())
+}
+
+fn palette() -> Palette<char> {
+    Palette::new(Color::rgb(200, 230, 140)) // open ground
+        .color('|', Color::rgb(34, 139, 34))
+        .color('#', Color::rgb(139, 90, 43))
+}
+
+fn render(landscape: &impl Acres) -> Image {
+    Image::from_text(&landscape.to_string(), &palette()).scale(4)
 }
 
 fn read_data(path: &str) -> Landscape {
+    read_data_with_rules(path, Landscape::default_rules())
+}
+
+fn read_data_with_rules(path: &str, rules: Rules2D) -> Landscape {
+    Landscape::with_rules(&fs::read_to_string(path).expect("Cannot open"), rules).unwrap()
+}
+
+fn read_packed(path: &str) -> PackedLandscape {
     fs::read_to_string(path).expect("Cannot open").parse().unwrap()
 }
 
 mod landscape {
-    use std::collections::hash_map::{HashMap, Entry};
+    use std::collections::HashMap;
     use std::fmt;
     use std::str::FromStr;
-    use crate::euclid::{point, Point, vector};
+    use crate::animation::Simulation;
+    use crate::automaton::{Automaton2D, Boundary, Period, Rules2D};
+    use crate::euclid::Vector;
+
+    const RULES: &str = include_str!("../data/day18-rules.txt");
 
     #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
     pub enum State {
@@ -35,161 +117,430 @@ mod landscape {
         YARD,
     }
 
+    // The operations needed to solve the puzzle, so that the backends are interchangeable
+    pub trait Acres: fmt::Display {
+        fn counts(&self) -> HashMap<State, usize>;
+
+        fn minute(&self) -> usize;
+
+        fn tick(&mut self, minutes: usize);
+
+        fn tick_to(&mut self, minute: usize) {
+            assert!(minute >= self.minute());
+            self.tick(minute - self.minute());
+        }
+
+        // Finds the cycle the landscape settles into, and ticks forward to the start of it if it
+        // isn't there already. After this tick() skips over any complete cycles.
+        fn find_period(&mut self) -> Period<Vector>;
+
+        // Ticks until the landscape repeats a state, i.e. to the end of the first cycle, and returns
+        // the length of the cycle
+        fn find_cycle_size(&mut self) -> usize {
+            let period = self.find_period();
+            self.tick(period.length as usize);
+            period.length as usize
+        }
+    }
+
+    impl State {
+        pub fn from_symbol(c: char) -> Option<State> {
+            match c {
+                '.' => Some(State::OPEN),
+                '|' => Some(State::TREES),
+                '#' => Some(State::YARD),
+                _ => None,
+            }
+        }
+    }
+
+    #[derive(Clone)]
     pub struct Landscape {
-        acres: HashMap<Point, State>,
-        minute: usize,
-        cycle_size: Option<usize>,
+        automaton: Automaton2D,
     }
 
     impl Landscape {
-        pub fn counts(&self) -> HashMap<State, usize> {
-            let mut counts = HashMap::new();
-            for state in self.acres.values() {
-                match counts.entry(*state) {
-                    Entry::Vacant(count) => { count.insert(1); },
-                    Entry::Occupied(mut count) => { *count.get_mut() += 1; },
-                }
-            }
-            counts
+        // Rules can define other states, but they won't be included in counts()
+        pub fn with_rules(s: &str, rules: Rules2D) -> Result<Landscape, String> {
+            let automaton = Automaton2D::parse(rules, s, Boundary::Fixed).map_err(|e| e.to_string())?;
+            Ok(Landscape { automaton })
         }
 
-        pub fn minute(&self) -> usize {
-            self.minute
+        pub fn default_rules() -> Rules2D {
+            RULES.parse().expect("Valid rules")
         }
+    }
 
-        pub fn tick_to(&mut self, minute: usize) {
-            assert!(minute >= self.minute);
-            self.tick(minute - self.minute);
+    impl Acres for Landscape {
+        fn counts(&self) -> HashMap<State, usize> {
+            let rules = self.automaton.rules();
+            self.automaton.counts().into_iter().enumerate()
+                .filter(|&(_, count)| count > 0)
+                .filter_map(|(state, count)| State::from_symbol(rules.symbol(state as u8)).map(|s| (s, count)))
+                .collect()
         }
 
-        pub fn tick(&mut self, minutes: usize) {
-            let minutes= match self.cycle_size {
-                Some(size) => minutes % size,
-                None => minutes,
-            };
-            for _ in 0..minutes {
-                self.tick_impl();
-            }
+        fn minute(&self) -> usize {
+            self.automaton.generation() as usize
+        }
+
+        fn tick(&mut self, minutes: usize) {
+            self.automaton.advance(minutes as u64);
+        }
+
+        fn find_period(&mut self) -> Period<Vector> {
+            let period = self.automaton.find_period();
+            self.tick_to(self.minute().max(period.start as usize));
+            period
+        }
+    }
+
+    // One minute per step; the landscape never stops changing, so callers should cap the frames
+    impl Simulation for Landscape {
+        fn step(&mut self) -> bool {
+            self.tick(1);
+            true
+        }
+
+        fn render(&self) -> String {
+            self.to_string()
+        }
+    }
+
+    impl FromStr for Landscape {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, String> {
+            Landscape::with_rules(s, Landscape::default_rules())
         }
+    }
 
-        pub fn find_cycle_size(&mut self) -> usize {
-            let mut points = self.acres.keys().cloned().collect::<Vec<_>>();
-            points.sort();
-            let points = points;
+    impl fmt::Display for Landscape {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.automaton)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
 
-            let to_cache_key = |acres: &HashMap<Point, State>|
-                points.iter().map(|p| *acres.get(p).expect("Unexpected point")).collect::<Vec<State>>();
+        #[test]
+        fn variant_rules() {
+            // Trees spread more easily, but lumberyards never appear
+            let rules = RULES
+                .replace("count(TREES) >= 3", "count(TREES) >= 2")
+                .replace("count(LUMBERYARD) >= 3", "count(LUMBERYARD) >= 9");
+            let mut landscape = Landscape::with_rules("|..\n.|.\n...", rules.parse().unwrap()).unwrap();
+            landscape.tick(1);
+            assert_eq!(landscape.to_string(), "||.\n||.\n...\n");
+            landscape.tick(1);
+            assert_eq!(landscape.counts(), [(State::OPEN, 1), (State::TREES, 8)].iter().cloned().collect());
+        }
+    }
+}
+pub use landscape::{Acres, Landscape, State};
+
+// A much faster, but fixed-rule, alternative to Landscape. Each row is stored as two bitsets, one
+// for trees and one for lumberyards, so that 64 acres can be updated at once with bitwise operations.
+// Neighbors are counted a row at a time, using bit-sliced adders: the count for each acre is a
+// 4-bit number, with bit n of every count stored together in one word.
+//   https://en.wikipedia.org/wiki/Bit_slicing
+mod packed {
+    use super::{Acres, State};
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::mem;
+    use std::str::FromStr;
+    use crate::animation::Simulation;
+    use crate::automaton::{self, Period, Periodic};
+    use crate::euclid::{vector, Vector};
+
+    #[derive(Clone, Eq, PartialEq)]
+    struct Planes {
+        trees: Vec<u64>,
+        yards: Vec<u64>,
+    }
+
+    impl Planes {
+        fn new(words: usize) -> Planes {
+            Planes { trees: vec![0; words], yards: vec![0; words] }
+        }
+    }
 
-            let mut seen_states = HashMap::new();
-            loop {
-                if let Some(start) = seen_states.insert(to_cache_key(&self.acres), seen_states.len()) {
-                    self.cycle_size = Some(seen_states.len() - start);
-                    break;
+    // Sums of up to 15 bitsets, where counts[n] holds bit n of each sum
+    type Counts = [u64; 4];
+
+    fn add(counts: &mut Counts, bits: u64) {
+        let mut carry = bits;
+        for count in counts.iter_mut() {
+            let next_carry = *count & carry;
+            *count ^= carry;
+            carry = next_carry;
+        }
+    }
+
+    fn at_least_one(counts: &Counts) -> u64 {
+        counts[0] | counts[1] | counts[2] | counts[3]
+    }
+
+    fn at_least_three(counts: &Counts) -> u64 {
+        (counts[0] & counts[1]) | counts[2] | counts[3]
+    }
+
+    // Current and next generations are double-buffered, so ticking doesn't allocate
+    #[derive(Clone)]
+    pub struct PackedLandscape {
+        width: usize,
+        height: usize,
+        words_per_row: usize,
+        current: Planes,
+        next: Planes,
+        minute: usize,
+        period: Option<Period<Vector>>,
+    }
+
+    impl PackedLandscape {
+        fn get(&self, x: usize, y: usize) -> State {
+            let (word, bit) = (y * self.words_per_row + x / 64, x % 64);
+            if self.current.trees[word] >> bit & 1 == 1 {
+                State::TREES
+            } else if self.current.yards[word] >> bit & 1 == 1 {
+                State::YARD
+            } else {
+                State::OPEN
+            }
+        }
+
+        // Adds the 8 neighbors of each acre in the given row of the plane, i.e. the row above
+        // and below, shifted in each direction, along with the row itself shifted in each direction
+        fn count_neighbors(&self, plane: &[u64], y: usize, i: usize) -> Counts {
+            let mut counts = [0; 4];
+            let words = self.words_per_row;
+            let word = |row: usize, i: usize| plane[row * words + i];
+            let mut add_row = |row: usize, include_center: bool| {
+                let center = word(row, i);
+                // bit x of west is the acre at x-1, and of east is the acre at x+1
+                let west = center << 1 | if i > 0 { word(row, i - 1) >> 63 } else { 0 };
+                let east = center >> 1 | if i + 1 < words { word(row, i + 1) << 63 } else { 0 };
+                add(&mut counts, west);
+                add(&mut counts, east);
+                if include_center {
+                    add(&mut counts, center);
                 }
-                assert!(seen_states.len() < 1000); // sanity check
-                self.tick_impl();
+            };
+            if y > 0 {
+                add_row(y - 1, true);
+            }
+            add_row(y, false);
+            if y + 1 < self.height {
+                add_row(y + 1, true);
             }
-            self.cycle_size.expect("Was just set")
+            counts
+        }
+
+        // Clears the bits past the width of the grid in the last word of a row
+        fn row_mask(&self, i: usize) -> u64 {
+            let bits = self.width - i * 64;
+            if bits >= 64 { u64::MAX } else { (1 << bits) - 1 }
         }
 
         fn tick_impl(&mut self) {
-            let mut new_acres = HashMap::new();
-
-            for (coord, state) in self.acres.iter() {
-                let neighbors = self.neighbors(coord);
-                let new_state = match state {
-                    State::OPEN => {
-                        if neighbors.into_iter().filter(|&s| s == State::TREES).count() >= 3 {
-                            State::TREES
-                        } else {
-                            *state
-                        }
-                    },
-                    State::TREES => {
-                        if neighbors.into_iter().filter(|&s| s == State::YARD).count() >= 3 {
-                            State::YARD
-                        } else {
-                            *state
-                        }
-                    },
-                    State::YARD => {
-                        if neighbors.contains(&State::YARD) && neighbors.contains(&State::TREES) {
-                            *state
-                        } else {
-                            State::OPEN
-                        }
-                    },
-                };
-                new_acres.insert(*coord, new_state);
-            }
+            for y in 0..self.height {
+                for i in 0..self.words_per_row {
+                    let index = y * self.words_per_row + i;
+                    let trees = self.current.trees[index];
+                    let yards = self.current.yards[index];
+                    let open = !(trees | yards) & self.row_mask(i);
+                    let tree_counts = self.count_neighbors(&self.current.trees, y, i);
+                    let yard_counts = self.count_neighbors(&self.current.yards, y, i);
 
+                    // Open acres with 3+ adjacent trees grow trees, trees with 3+ adjacent
+                    // lumberyards become lumberyards, and lumberyards only remain if they're next to
+                    // both trees and another lumberyard
+                    let to_trees = open & at_least_three(&tree_counts);
+                    let to_yards = trees & at_least_three(&yard_counts);
+                    let keep_yards = yards & at_least_one(&yard_counts) & at_least_one(&tree_counts);
+                    self.next.trees[index] = to_trees | (trees & !to_yards);
+                    self.next.yards[index] = to_yards | keep_yards;
+                }
+            }
+            mem::swap(&mut self.current, &mut self.next);
             self.minute += 1;
-            self.acres = new_acres
         }
+    }
 
-        fn neighbors(&self, coord: &Point) -> Vec<State> {
-            let mut result = Vec::new();
-            for x in -1..2 {
-                for y in -1..2 {
-                    if x == 0 && y == 0 { continue; }
-                    if let Some(state) = self.acres.get(&(coord + vector(x, y))) {
-                        result.push(*state);
-                    }
+    impl Acres for PackedLandscape {
+        fn counts(&self) -> HashMap<State, usize> {
+            let trees = self.current.trees.iter().map(|w| w.count_ones() as usize).sum();
+            let yards = self.current.yards.iter().map(|w| w.count_ones() as usize).sum();
+            let counts = [(State::OPEN, self.width * self.height - trees - yards), (State::TREES, trees), (State::YARD, yards)];
+            counts.iter().cloned().filter(|&(_, count)| count > 0).collect()
+        }
+
+        fn minute(&self) -> usize {
+            self.minute
+        }
+
+        fn tick(&mut self, minutes: usize) {
+            let target = self.minute + minutes;
+            while self.minute < target {
+                match self.period {
+                    Some(period) if self.minute as u64 >= period.start => {
+                        period.advance_to(self, target as u64);
+                        return;
+                    },
+                    _ => self.tick_impl(),
                 }
             }
-            result
+        }
+
+        fn find_period(&mut self) -> Period<Vector> {
+            let period = automaton::find_period(self);
+            self.tick_to(self.minute().max(period.start as usize));
+            self.period = Some(period);
+            period
         }
     }
 
-    impl FromStr for Landscape {
+    // The landscape is bounded, so its patterns can only repeat in place
+    impl Periodic for PackedLandscape {
+        type Shift = Vector;
+
+        fn generation(&self) -> u64 {
+            self.minute as u64
+        }
+
+        fn tick(&mut self) {
+            self.tick_impl();
+        }
+
+        fn shift_from(&self, earlier: &PackedLandscape) -> Option<Vector> {
+            if self.current == earlier.current { Some(vector(0, 0)) } else { None }
+        }
+
+        fn skip_periods(&mut self, period: &Period<Vector>, count: u64) {
+            self.minute += (period.length * count) as usize;
+        }
+    }
+
+    // One minute per step; the landscape never stops changing, so callers should cap the frames
+    impl Simulation for PackedLandscape {
+        fn step(&mut self) -> bool {
+            self.tick_impl();
+            true
+        }
+
+        fn render(&self) -> String {
+            self.to_string()
+        }
+    }
+
+    impl FromStr for PackedLandscape {
         type Err = String;
 
         fn from_str(s: &str) -> Result<Self, String> {
-            let mut acres =  HashMap::new();
-            let mut coord = point(0, 0);
-
-            for c in s.chars() {
-                match c {
-                    '.' => { acres.insert(coord, State::OPEN); },
-                    '|' => { acres.insert(coord, State::TREES); },
-                    '#' => { acres.insert(coord, State::YARD); },
-                    '\n' => { coord = point(-1, coord.y + 1); },
-                    _ => { return Err(format!("Unexpected char {} at {}", c, coord)); },
-                };
-                coord = point(coord.x + 1, coord.y);
+            let lines: Vec<_> = s.lines().collect();
+            let width = lines.first().map_or(0, |l| l.chars().count());
+            if width == 0 {
+                return Err("Empty landscape".into());
             }
-
-            Ok(Landscape { acres, minute: 0, cycle_size: None })
+            let words_per_row = width.div_ceil(64);
+            let mut current = Planes::new(words_per_row * lines.len());
+            for (y, line) in lines.iter().enumerate() {
+                if line.chars().count() != width {
+                    return Err(format!("Row {} has length {}, expected {}", y, line.chars().count(), width));
+                }
+                for (x, c) in line.chars().enumerate() {
+                    let (word, bit) = (y * words_per_row + x / 64, x % 64);
+                    match State::from_symbol(c) {
+                        Some(State::OPEN) => {},
+                        Some(State::TREES) => current.trees[word] |= 1 << bit,
+                        Some(State::YARD) => current.yards[word] |= 1 << bit,
+                        None => return Err(format!("Unexpected '{}' at {},{}", c, x, y)),
+                    }
+                }
+            }
+            let next = Planes::new(current.trees.len());
+            Ok(PackedLandscape { width, height: lines.len(), words_per_row, current, next, minute: 0, period: None })
         }
     }
 
-    impl fmt::Display for Landscape {
+    impl fmt::Display for PackedLandscape {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            if self.acres.is_empty() {
-                return write!(f, "");
-            }
-
-            let mut out = String::new();
-            let (_, max) = Point::bounding_box(self.acres.keys().cloned())
-                .expect("acres is not-empty");
-            for y in 0..max.y+1 {
-                for x in 0..max.x+1 {
-                    let coord = point(x, y);
-                    let c = match self.acres.get(&coord) {
-                        Some(State::OPEN) => '.',
-                        Some(State::TREES) => '|',
-                        Some(State::YARD) => '#',
-                        None => panic!("Unexpected coord: {}", coord),
-                    };
-                    out.push(c);
+            let mut out = String::with_capacity((self.width + 1) * self.height);
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    out.push(match self.get(x, y) {
+                        State::OPEN => '.',
+                        State::TREES => '|',
+                        State::YARD => '#',
+                    });
                 }
                 out.push('\n');
             }
             write!(f, "{}", out)
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{add, at_least_one, at_least_three, Acres, PackedLandscape};
+        use super::super::{read_data, read_packed, Landscape, REAL_DATA, TEST_DATA};
+
+        #[test]
+        fn adder() {
+            let mut counts = [0; 4];
+            for bits in &[0b1111, 0b0111, 0b0011, 0b0001] {
+                add(&mut counts, *bits);
+            }
+            // Bit n of the inputs sums to 4 - n
+            assert_eq!(counts, [0b1010, 0b0110, 0b0001, 0]);
+            assert_eq!(at_least_three(&counts), 0b0011);
+            assert_eq!(at_least_one(&counts), 0b1111);
+        }
+
+        #[test]
+        fn wide_rows() {
+            // Trees spread across the boundary between words
+            let row = format!("{}||{}", ".".repeat(62), ".".repeat(70));
+            let blank = ".".repeat(134);
+            let landscape = format!("{}\n{}\n{}\n", blank, row, row);
+            let mut packed: PackedLandscape = landscape.parse().unwrap();
+            let mut general: Landscape = landscape.parse().unwrap();
+            assert_eq!(packed.to_string(), landscape);
+            for _ in 0..5 {
+                packed.tick(1);
+                general.tick(1);
+                assert_eq!(packed.to_string(), general.to_string());
+            }
+            assert_eq!(packed.counts(), general.counts());
+        }
+
+        #[test]
+        fn matches_general() {
+            // The example exercises lumberyards, which wide_rows doesn't
+            for path in &[TEST_DATA, REAL_DATA] {
+                let mut packed = read_packed(path);
+                let mut general = read_data(path);
+                for _ in 0..20 {
+                    packed.tick(1);
+                    general.tick(1);
+                    assert_eq!(packed.to_string(), general.to_string());
+                    assert_eq!(packed.counts(), general.counts());
+                }
+
+                assert_eq!(packed.find_cycle_size(), general.find_cycle_size());
+                assert_eq!(packed.minute(), general.minute());
+                assert_eq!(packed.to_string(), general.to_string());
+                packed.tick_to(1000);
+                general.tick_to(1000);
+                assert_eq!(packed.counts(), general.counts());
+            }
+        }
+    }
 }
-pub use landscape::{Landscape, State};
+pub use packed::PackedLandscape;
 
 #[cfg(test)]
 mod tests {
@@ -203,7 +554,15 @@ mod tests {
 
     #[test]
     fn example() {
-        let mut landscape = read_data(TEST_DATA);
+        check_example(read_data(TEST_DATA));
+    }
+
+    #[test]
+    fn example_packed() {
+        check_example(read_packed(TEST_DATA));
+    }
+
+    fn check_example(mut landscape: impl Acres) {
         landscape.tick(10);
         println!("{}", landscape);
         let counts: HashMap<State, usize> =
@@ -214,9 +573,24 @@ mod tests {
         assert_eq!(landscape.counts(), counts);
 
         assert_eq!(landscape.find_cycle_size(), 1);
+        assert_eq!(landscape.minute(), 19);
 
         let counts: HashMap<State, usize> = [(State::OPEN, 100)]
                 .iter().cloned().collect();
         assert_eq!(landscape.counts(), counts);
+
+        // Already in the cycle, so this doesn't need to tick
+        let minute = landscape.minute();
+        assert_eq!(landscape.find_period().length, 1);
+        assert_eq!(landscape.minute(), minute);
+    }
+
+    #[test]
+    fn frames() {
+        let mut landscape = read_data(TEST_DATA);
+        let frames: Vec<_> = crate::animation::Frames::new(&mut landscape, 11).collect();
+        assert_eq!(landscape.minute(), 10);
+        assert_eq!(frames.last(), Some(&landscape.to_string()));
+        assert_eq!(frames[0], read_data(TEST_DATA).to_string());
     }
 }
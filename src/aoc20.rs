@@ -34,7 +34,7 @@ mod ast {
     use std::str::FromStr;
     use std::fmt::Write;
     use itertools::Itertools;
-    use crate::euclid::{point, Point, vector, Vector};
+    use crate::euclid::{point, Point, Direction};
     use std::collections::HashSet;
 
     #[derive(Eq, PartialEq, Debug)]
@@ -45,21 +45,11 @@ mod ast {
     }
 
     impl Path {
-        fn dir_to_vec(dir: char) -> Vector {
-            match dir {
-                'N' => vector(0, -1),
-                'S' => vector(0, 1),
-                'E' => vector(1, 0),
-                'W' => vector( -1, 0),
-                x => panic!("Invalid dir: {}", x),
-            }
-        }
-
         fn visit<F>(&self, coord: Point, visitor: &mut F) -> HashSet<Point>
             where F: FnMut(Point, Point) {
             match self {
                 Path::LITERAL(dir) => {
-                    let next_coord = coord + Path::dir_to_vec(*dir);
+                    let next_coord = coord + Direction::from_char(*dir).expect("Invalid dir").as_vector();
                     visitor(coord, next_coord);
                     [next_coord].iter().cloned().collect()
                 },
@@ -142,8 +142,9 @@ mod explorer {
 
     impl Graph for Explorer {
         type Node = Status;
+        type Cost = i32;
 
-        fn neighbors(&self, source: &Self::Node) -> Vec<Edge<Self::Node>> {
+        fn neighbors(&self, source: &Self::Node) -> Vec<Edge<Self::Node, Self::Cost>> {
             let Status { region, tool } = source;
 
             let mut neighbors: Vec<_> = vec!(vector(0, 1), vector(1, 0), vector(0, -1), vector(-1, 0)).iter()
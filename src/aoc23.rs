@@ -2,7 +2,8 @@ use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::collections::BinaryHeap;
 use std::cmp::Ordering;
-use crate::euclid3d::{Point,vector};
+// Coordinates are in the hundreds of millions, so distances between them can overflow an i32
+use crate::euclid3d::wide::{Cuboid,ManhattanBall,Point,vector};
 
 pub fn advent() {
     let nanobots = read_data();
@@ -72,7 +73,7 @@ mod score {
     impl Score {
         pub fn new(region: Region, nanobots: &[Nanobot]) -> Score {
             let in_range = nanobots.iter()
-                .filter(|b| (&region).distance_to(&b.pos()) <= b.radius())
+                .filter(|b| b.signal().intersects_box(&region.bounds()))
                 .count() as u32;
             Score { region, in_range }
         }
@@ -106,60 +107,32 @@ pub use self::score::Score;
 mod region {
     use super::*;
 
+    // A cube with power-of-two sides, so it can be repeatedly split down to a single point
     #[derive(Copy, Clone, Debug)]
     pub struct Region {
-        origin: Point,
-        size: u32,
+        bounds: Cuboid,
     }
 
     impl Region {
-        fn new(origin: Point, size: u32) -> Region {
-            assert!(size >= 1);
-            Region { origin, size }
-        }
-
         pub fn from_min_max(min: Point, max: Point) -> Region {
-            assert!(min.x <= max.x);
-            assert!(min.y <= max.y);
-            assert!(min.z <= max.z);
-            Region::new(min, (max - min).grid_len().next_power_of_two())
+            let size = (max - min).grid_len().next_power_of_two() as i64;
+            Region { bounds: Cuboid::with_size(min, vector(size, size, size)) }
         }
 
-        pub fn origin(&self) -> Point { self.origin }
-        pub fn size(&self) -> u32 { self.size }
+        pub fn bounds(&self) -> Cuboid { self.bounds }
+        pub fn origin(&self) -> Point { self.bounds.min() }
+        pub fn size(&self) -> u64 { self.bounds.size().x as u64 }
 
         pub fn as_point(&self) -> Option<Point> {
-            match self.size {
-                1 => Some(self.origin),
+            match self.size() {
+                1 => Some(self.origin()),
                 _ => None,
             }
         }
 
-        pub fn distance_to(&self, coord: &Point) -> u32 {
-            let s = self.size as i32 - 1;
-            let axis_dist = |o, c| {
-                if c < o { o - c } else if c > o + s { c - (o + s) } else { 0 }
-            };
-
-            (axis_dist(self.origin.x, coord.x).abs()
-                + axis_dist(self.origin.y, coord.y).abs()
-                + axis_dist(self.origin.z, coord.z).abs()) as u32
-        }
-
         pub fn split(&self) -> Vec<Region> {
-            assert!(self.size > 1); // sanity-check
-            let size = self.size / 2;
-            let s = size as i32;
-            vec!(
-                vector(0, 0, 0),
-                vector(s, 0, 0),
-                vector(0, s, 0),
-                vector(0, 0, s),
-                vector(s, s, 0),
-                vector(s, 0, s),
-                vector(0, s, s),
-                vector(s, s, s),
-            ).iter().map(|v| self.origin + v).map(|o| Region::new(o, size)).collect()
+            assert!(self.size() > 1); // sanity-check
+            self.bounds.split().into_iter().map(|bounds| Region { bounds }).collect()
         }
     }
 }
@@ -173,18 +146,21 @@ mod nanobot {
     #[derive(Debug, Copy, Clone, Eq, PartialEq)]
     pub struct Nanobot {
         pos: Point,
-        radius: u32,
+        radius: u64,
     }
 
     impl Nanobot {
         #[inline]
-        pub fn new(pos: Point, radius: u32) -> Nanobot { Nanobot { pos, radius } }
+        pub fn new(pos: Point, radius: u64) -> Nanobot { Nanobot { pos, radius } }
 
         pub fn pos(&self) -> Point { self.pos }
-        pub fn radius(&self) -> u32 { self.radius }
+        pub fn radius(&self) -> u64 { self.radius }
+
+        // The region the bot's signal reaches
+        pub fn signal(&self) -> ManhattanBall { ManhattanBall::new(self.pos, self.radius) }
 
         pub fn reachable(&self, target: &Point) -> bool {
-            (self.pos - target).grid_len() <= self.radius
+            self.signal().contains(target)
         }
     }
 
@@ -198,7 +174,7 @@ mod nanobot {
 
             let caps = regex_captures!(RE, s)?;
             let pos: Point = capture_group!(caps, 1).parse().map_err(|_| "NOPE".to_string())?;
-            let radius: u32 = capture_group!(caps, 2).parse().map_err(|_| "NOPE".to_string())?;
+            let radius: u64 = capture_group!(caps, 2).parse().map_err(|_| "NOPE".to_string())?;
             Ok(Nanobot::new(pos, radius))
         }
     }
@@ -208,7 +184,7 @@ pub use self::nanobot::Nanobot;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::euclid3d::point;
+    use crate::euclid3d::wide::point;
 
     #[test]
     fn read_file() { read_data(); }
@@ -1,23 +1,19 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use crate::euclid::Grid;
 
 pub fn advent() {
     let claims = read_data();
-    let mut fabric = grid::Grid::new(1000, 1000);
+    let mut fabric = Grid::new(1000, 1000, 0);
     for claim in &claims {
         record_claim(&claim, &mut fabric);
     }
 
-    let mut conflicts = 0;
-    for inch in fabric.iter() {
-        if *inch.2 > 1 {
-            conflicts += 1;
-        }
-    }
+    let conflicts = fabric.iter().filter(|&(_, &claims)| claims > 1).count();
     println!("Conflicts: {}", conflicts);
 
     for claim in &claims {
-        if check_claim(&claim, &fabric) {
+        if check_claim(&claim, &claims) {
             println!("Valid Claim: {}", claim.id);
             return;
         }
@@ -25,27 +21,16 @@ pub fn advent() {
     panic!("No valid claims found.");
 }
 
-fn record_claim(claim: &claim::Claim, fabric: &mut grid::Grid) {
-    for x in claim.x..claim.x+claim.w {
-        for y in claim.y..claim.y+claim.h {
-            fabric.incr(x, y);
-        }
+fn record_claim(claim: &claim::Claim, fabric: &mut Grid<i32>) {
+    for inch in claim.rect().points() {
+        fabric[inch] += 1;
     }
 }
 
-fn check_claim(claim: &claim::Claim, fabric: &grid::Grid) -> bool {
-    for x in claim.x..claim.x+claim.w {
-        for y in claim.y..claim.y+claim.h {
-            let claims = fabric[(x, y)];
-            if claims < 1 {
-                panic!("Coordinate [{}, {}] should have already been claimed", x, y);
-            }
-            if claims > 1 {
-                return false;
-            }
-        }
-    }
-    return true;
+// A claim is valid if it doesn't overlap any other claim
+fn check_claim(claim: &claim::Claim, claims: &[claim::Claim]) -> bool {
+    let rect = claim.rect();
+    claims.iter().all(|other| other.id == claim.id || !rect.intersects(&other.rect()))
 }
 
 fn read_data() -> Vec<claim::Claim> {
@@ -61,6 +46,7 @@ fn read_data() -> Vec<claim::Claim> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::euclid::point;
 
     #[test]
     fn read_file() {
@@ -70,10 +56,10 @@ mod tests {
     #[test]
     fn record_claims() {
         let expected = {
-            let mut expected = grid::Grid::new(11, 9);
+            let mut expected = Grid::new(11, 9, 0);
             for x in 3..8 {
                 for y in 2..6 {
-                    expected.incr(x, y);
+                    expected[point(x, y)] += 1;
                 }
             }
             expected
@@ -82,7 +68,7 @@ mod tests {
 
         let claim: claim::Claim = "#123 @ 3,2: 5x4".parse::<>().unwrap();
 
-        let mut fabric = grid::Grid::new(11, 9);
+        let mut fabric = Grid::new(11, 9, 0);
         record_claim(&claim, &mut fabric);
         assert_eq!(fabric, expected);
     }
@@ -91,15 +77,9 @@ mod tests {
     fn check_claims() {
         let claims: Vec<_> = ["#1 @ 1,3: 4x4", "#2 @ 3,1: 4x4", "#3 @ 5,5: 2x2"].iter()
             .map(|s| s.parse().unwrap()).collect();
-        let mut fabric = grid::Grid::new(8, 8);
-
-        for claim in &claims {
-            record_claim(&claim, &mut fabric);
-        }
-
         let mut valid_claim= 0;
         for claim in &claims {
-            if check_claim(&claim, &fabric) {
+            if check_claim(&claim, &claims) {
                 valid_claim = claim.id;
             }
         }
@@ -107,141 +87,13 @@ mod tests {
     }
 }
 
-// Models a two dimensional grid backed by a single Vec.
-// Initially considered a 2D array, like
-// https://www.reddit.com/r/rust/comments/3l0dau/dynamic_heapallocated_multidimensional_arrays/cv2fn5y
-// but the type signatures were odd, at best.
-mod grid {
-    use std::fmt;
-    use std::fmt::Write;
-    use std::ops::Index;
-
-    #[derive(Debug, Eq, PartialEq)]
-    pub struct Grid {
-        grid: Vec<i32>,
-        width: usize,
-    }
-
-    impl Grid {
-        pub fn new(width: usize, height: usize) -> Grid {
-            Grid { grid: vec![0; width * height], width }
-        }
-
-        pub fn get(&self, x: usize, y: usize) -> Option<&i32> {
-            self.grid.get(x + self.width * y)
-        }
-
-        pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut i32> {
-            self.grid.get_mut(x + self.width * y)
-        }
-
-        pub fn incr(&mut self, x: usize, y: usize) {
-            *self.get_mut(x, y).unwrap() += 1;
-        }
-
-        pub fn iter<'a>(&'a self) -> GridIter<'a> {
-            GridIter {
-                inner: self,
-                pos: 0,
-            }
-        }
-    }
-
-    impl Index<(usize, usize)> for Grid {
-        type Output = i32;
-
-        fn index(&self, coordinate: (usize, usize)) -> &i32 {
-            self.get(coordinate.0, coordinate.1).unwrap()
-        }
-    }
-
-    impl fmt::Display for Grid {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            // See also https://stackoverflow.com/q/30320083#comment92146674_30320443
-            let mut out = String::new();
-            for i in 0..self.grid.len() {
-                let value = match self.grid[i] {
-                    v if v < 10 => v.to_string(),
-                    _ => "*".to_string(),
-                };
-                write!(&mut out, " {}", value).expect("writing to string");
-                if i % self.width == self.width - 1 {
-                    out.push('\n');
-                }
-            }
-            write!(f, "{}", out)
-        }
-    }
-
-    // https://blog.guillaume-gomez.fr/articles/2017-03-09+Little+tour+of+multiple+iterators+implementation+in+Rust
-    pub struct GridIter<'a> {
-        inner: &'a Grid,
-        pos: usize,
-    }
-
-    impl<'a> Iterator for GridIter<'a> {
-        type Item = (usize, usize, &'a i32);
-
-        fn next(&mut self) -> Option<Self::Item> {
-            if self.pos >= self.inner.grid.len() {
-                None
-            } else {
-                self.pos += 1;
-                let pos = self.pos - 1;
-                let x = pos % self.inner.width;
-                let y = pos / self.inner.width;
-                self.inner.get(x, y).map(|v| (x, y, v))
-            }
-        }
-    }
-
-    #[cfg(test)]
-    mod tests {
-        use super::*;
-
-        #[test]
-        fn grid_str() {
-            let grid = Grid::new(2, 3);
-            assert_eq!(grid.to_string(), " 0 0\n 0 0\n 0 0\n");
-        }
-
-        #[test]
-        fn grid_incr() {
-            let mut grid = Grid::new(5, 5);
-            assert_eq!(grid[(2, 2)], 0);
-            grid.incr(2, 2);
-            assert_eq!(grid[(2, 2)], 1);
-        }
-
-        #[test]
-        fn grid_bounds() {
-            let mut grid = Grid::new(3, 2);
-            *grid.get_mut(0, 0).unwrap() = 1;
-            *grid.get_mut(2, 0).unwrap() = 2;
-            *grid.get_mut(0, 1).unwrap() = 3;
-            *grid.get_mut(2, 1).unwrap() = 4;
-            assert_eq!(grid.to_string(), " 1 0 2\n 3 0 4\n");
-        }
-
-        #[test]
-        fn grid_iter() {
-            let grid = Grid::new(2, 2);
-            let mut grid_iter = grid.iter();
-            assert_eq!(grid_iter.next().unwrap(), (0, 0, &0));
-            assert_eq!(grid_iter.next().unwrap(), (1, 0, &0));
-            assert_eq!(grid_iter.next().unwrap(), (0, 1, &0));
-            assert_eq!(grid_iter.next().unwrap(), (1, 1, &0));
-            assert_eq!(grid_iter.next(), None);
-        }
-    }
-}
-
 mod claim {
     use std::error;
     use std::fmt;
     use std::num;
     use std::str::FromStr;
     use regex::{Captures, Regex};
+    use crate::euclid::{point, vector, Rect};
 
     // https://blog.burntsushi.net/rust-error-handling/
     #[derive(Debug)]
@@ -286,6 +138,12 @@ mod claim {
         pub h: usize
     }
 
+    impl Claim {
+        pub fn rect(&self) -> Rect {
+            Rect::with_size(point(self.x as i32, self.y as i32), vector(self.w as i32, self.h as i32))
+        }
+    }
+
     type Result<T> = std::result::Result<T, ClaimError>;
 
     impl FromStr for Claim {
@@ -344,6 +202,7 @@ mod claim {
         fn examples_part1() {
             let claim: Claim = "#123 @ 3,2: 5x4".parse().unwrap();
             assert_eq!(claim, Claim { id: 123, x: 3, y: 2, w: 5, h: 4});
+            assert_eq!(claim.rect(), Rect::new(point(3, 2), point(7, 5)));
         }
     }
 }
@@ -3,16 +3,21 @@ use std::char;
 use std::fmt;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use crate::euclid::{point,Point};
+use crate::euclid::{Point,SparseGrid};
+use crate::image::{self, Color, Image, Palette};
 
-pub fn advent() {
+pub fn advent(args: &[String]) {
     let coords = read_data();
     let labels = label_points(&coords);
     let grid = coverage_for_points(&labels);
 
     // takes up ~360 columns
-    //println!("Final:\n{}", grid);
+    //println!("Final:\n{}", grid.render(|c| c.map_or(' ', |c| c.to_string().remove(0))));
     println!("Largest Area: {}", find_largest_enclosed_area(&labels, &grid));
+    if let Some(path) = image::render_path(args) {
+        render(&labels, &grid).save(path).expect("Cannot render");
+        println!("Rendered to {}", path);
+    }
 
     let grid = sum_distances(&coords);
     println!("Less than 10k: {}", count_lessthan(&grid, 10000));
@@ -43,15 +48,15 @@ impl fmt::Display for Coordinate {
     }
 }
 
-fn populate_distances(grid: &mut taxicab::Grid<Coordinate>, start_point: Point) {
-    match grid.get(start_point) {
+fn populate_distances(grid: &mut SparseGrid<Coordinate>, start_point: Point) {
+    match grid.get(&start_point) {
         Some(Coordinate::Labeled(label)) => {
-            // eager copy so the reference isn't passed into the closure
+            // eager copy so the reference isn't held while the grid is updated
             let label = label.to_string();
-            for point in grid.points_iter() {
+            for point in grid.points() {
                 let distance = (point - start_point).grid_len();
-                grid.get_entry(point)
-                    .and_modify(|e| {
+                match grid.get_mut(&point) {
+                    Some(e) => {
                         match e {
                             Coordinate::Labeled(_) => {},
                             Coordinate::Equidistant(d) => {
@@ -67,8 +72,9 @@ fn populate_distances(grid: &mut taxicab::Grid<Coordinate>, start_point: Point)
                                 }
                             }
                         }
-                    })
-                    .or_insert(Coordinate::Nearest(label.clone(), distance));
+                    },
+                    None => { grid.insert(point, Coordinate::Nearest(label.clone(), distance)); },
+                }
             }
         },
         x => panic!("Unexpected value in {}: {:?}", start_point, x),
@@ -82,8 +88,8 @@ fn label_points(points: &Vec<Point>) -> BTreeMap<String, Point> {
     points.iter().map(|p| (l_gen(), *p)).collect()
 }
 
-fn coverage_for_points(labels: &BTreeMap<String, Point>) -> taxicab::Grid<Coordinate> {
-    let mut grid = taxicab::grid();
+fn coverage_for_points(labels: &BTreeMap<String, Point>) -> SparseGrid<Coordinate> {
+    let mut grid = SparseGrid::new();
     for (label, point) in labels.iter() {
         grid.insert(*point, Coordinate::Labeled(label.clone()));
     }
@@ -96,26 +102,28 @@ fn coverage_for_points(labels: &BTreeMap<String, Point>) -> taxicab::Grid<Coordi
     grid
 }
 
-fn compute_area(grid: &taxicab::Grid<Coordinate>, label_point: Point) -> u32 {
-    let mut sum = 0;
-    let label = match grid.get(label_point).expect("absent") {
+// Each label's area gets its own color, with the labeled points themselves in white
+fn render(labels: &BTreeMap<String, Point>, grid: &SparseGrid<Coordinate>) -> Image {
+    let palette = Palette::distinct(labels.keys(), Color::BLACK);
+    Image::from_sparse(grid, |c| match c {
+        Some(Coordinate::Labeled(_)) => Color::WHITE,
+        Some(Coordinate::Nearest(l, _)) => palette.get(&l),
+        Some(Coordinate::Equidistant(_)) | None => Color::BLACK,
+    })
+}
+
+fn compute_area(grid: &SparseGrid<Coordinate>, label_point: Point) -> u32 {
+    let label = match grid.get(&label_point).expect("absent") {
         Coordinate::Labeled(s) => s,
         _ => panic!("expected label"),
     };
-    for point in grid.points_iter() {
-        if let Some(c) = grid.get(point) {
-            if let Coordinate::Labeled(s) = c {
-                if s == label { sum += 1; }
-            }
-            if let Coordinate::Nearest(s, _) = c {
-                if s == label { sum += 1; }
-            }
-        }
-    }
-    sum
+    grid.iter().filter(|(_, c)| match c {
+        Coordinate::Labeled(s) | Coordinate::Nearest(s, _) => s == label,
+        Coordinate::Equidistant(_) => false,
+    }).count() as u32
 }
 
-fn find_largest_enclosed_area(labels: &BTreeMap<String, Point>, grid: &taxicab::Grid<Coordinate>) -> u32 {
+fn find_largest_enclosed_area(labels: &BTreeMap<String, Point>, grid: &SparseGrid<Coordinate>) -> u32 {
     let mut enclosed_labels: HashSet<_> = labels.keys().collect();
     let mut remove_label = |c: Option<&Coordinate>| {
         match c.expect("within bounds and already populated") {
@@ -125,14 +133,9 @@ fn find_largest_enclosed_area(labels: &BTreeMap<String, Point>, grid: &taxicab::
         }
     };
 
-    let (min, max) = grid.bounds().expect("No points in empty grid");
-    for x in min.x..max.x {
-        remove_label(grid.get(point(x, min.y)));
-        remove_label(grid.get(point(x, max.y)));
-    }
-    for y in min.y..max.y {
-        remove_label(grid.get(point(min.x, y)));
-        remove_label(grid.get(point(max.x, y)));
+    // Areas reaching the edge of the grid extend infinitely
+    for point in grid.border() {
+        remove_label(grid.get(&point));
     }
 
     let mut largest = None;
@@ -148,26 +151,27 @@ fn find_largest_enclosed_area(labels: &BTreeMap<String, Point>, grid: &taxicab::
     largest.expect("present").1
 }
 
-fn sum_distances(coords: &Vec<Point>) -> taxicab::Grid<u32> {
-    let mut grid = taxicab::grid();
+fn sum_distances(coords: &Vec<Point>) -> SparseGrid<u32> {
+    let mut grid = SparseGrid::new();
     for coord in coords.iter() {
         grid.expand_bounds(*coord);
     }
 
-    for point in grid.points_iter() {
+    for point in grid.points() {
         let sum: u32 = coords.iter().map(|&c| (c - point).grid_len()).sum();
         grid.insert(point, sum);
     }
     grid
 }
 
-fn count_lessthan(grid: &taxicab::Grid<u32>, limit: u32) -> u32 {
-    grid.points_iter().map(|p| if *grid.get(p).unwrap() < limit { 1 } else { 0 }).sum()
+fn count_lessthan(grid: &SparseGrid<u32>, limit: u32) -> u32 {
+    grid.iter().filter(|&(_, &sum)| sum < limit).count() as u32
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::euclid::point;
 
     #[test]
     fn read_file() {
@@ -176,23 +180,23 @@ mod tests {
 
     #[test]
     fn distances() {
-        let mut grid: taxicab::Grid<Coordinate> = taxicab::grid();
+        let mut grid: SparseGrid<Coordinate> = SparseGrid::new();
         grid.insert(point(2, 2), Coordinate::Labeled("A".into()));
         grid.insert(point(4, 2), Coordinate::Labeled("B".into()));
         grid.insert(point(1, 1), Coordinate::Equidistant(10));
         grid.insert(point(4, 4), Coordinate::Equidistant(10));
 
         populate_distances(&mut grid, point(2, 2));
-        assert_eq!(grid.get(point(1, 1)), Some(&Coordinate::Nearest("A".into(), 2)));
-        assert_eq!(grid.get(point(4, 4)), Some(&Coordinate::Nearest("A".into(), 4)));
+        assert_eq!(grid.get(&point(1, 1)), Some(&Coordinate::Nearest("A".into(), 2)));
+        assert_eq!(grid.get(&point(4, 4)), Some(&Coordinate::Nearest("A".into(), 4)));
 
         populate_distances(&mut grid, point(4, 2));
-        assert_eq!(grid.get(point(1, 1)),  Some(&Coordinate::Nearest("A".into(), 2)));
-        assert_eq!(grid.get(point(4, 4)),  Some(&Coordinate::Nearest("B".into(), 2)));
-        assert_eq!(grid.get(point(3, 2)),  Some(&Coordinate::Equidistant(1)));
-        assert_eq!(grid.get(point(3, 3)), Some(&Coordinate::Equidistant(2)));
-        assert_eq!(grid.get(point(2, 2)),  Some(&Coordinate::Labeled("A".into())));
-        assert_eq!(grid.get(point(4, 2)),  Some(&Coordinate::Labeled("B".into())));
+        assert_eq!(grid.get(&point(1, 1)),  Some(&Coordinate::Nearest("A".into(), 2)));
+        assert_eq!(grid.get(&point(4, 4)),  Some(&Coordinate::Nearest("B".into(), 2)));
+        assert_eq!(grid.get(&point(3, 2)),  Some(&Coordinate::Equidistant(1)));
+        assert_eq!(grid.get(&point(3, 3)), Some(&Coordinate::Equidistant(2)));
+        assert_eq!(grid.get(&point(2, 2)),  Some(&Coordinate::Labeled("A".into())));
+        assert_eq!(grid.get(&point(4, 2)),  Some(&Coordinate::Labeled("B".into())));
     }
 
     #[test]
@@ -213,6 +217,12 @@ mod tests {
         assert_eq!(compute_area(&grid, point(8, 9)), 10);
 
         assert_eq!(find_largest_enclosed_area(&labels, &grid), 17);
+
+        let image = render(&labels, &grid);
+        assert_eq!((image.width(), image.height()), (8, 9));
+        assert_eq!(image.get(0, 0), Color::WHITE);
+        assert_eq!(image.get(1, 0), Color::hue(0));
+        assert_eq!(image.get(4, 0), Color::BLACK);
     }
 
     #[test]
@@ -225,139 +235,3 @@ mod tests {
         assert_eq!(count_lessthan(&grid, 32), 16);
     }
 }
-
-// https://en.wikipedia.org/wiki/Taxicab_geometry
-mod taxicab {
-    use std::collections::HashMap;
-    use std::collections::hash_map::Entry;
-    use std::cmp;
-    use std::fmt;
-    use std::fmt::Write;
-    use crate::euclid::{point,Point};
-
-    fn points_between(min: Point, max: Point) -> impl Iterator<Item = Point> {
-        assert!(min.x <= max.x);
-        assert!(min.y <= max.y);
-        // https://users.rust-lang.org/t/product-of-iterators/2219
-        // notice the x,y order is flipped, so that we iterate left-to-right first
-        iproduct!(min.y..max.y+1, min.x..max.x+1).map(|p| point(p.1, p.0))
-    }
-
-    #[derive(Debug, PartialEq, Eq)]
-    pub struct Grid<T> {
-        map: HashMap<Point, T>,
-        min_max: Option<(Point, Point)>,
-    }
-
-    #[inline]
-    pub fn grid<T>() -> Grid<T> {
-        Grid { map: HashMap::new(), min_max: None }
-    }
-
-    impl<T> Grid<T> {
-        pub fn insert(&mut self, k: Point, v: T) {
-            self.expand_bounds(k);
-            self.map.insert(k, v);
-        }
-
-        pub fn expand_bounds(&mut self, p: Point) {
-            match self.min_max {
-                Some((min, max)) => {
-                    let min = point(cmp::min(min.x, p.x), cmp::min(min.y, p.y));
-                    let max = point(cmp::max(max.x, p.x), cmp::max(max.y, p.y));
-                    self.min_max = Some((min, max));
-                },
-                None => self.min_max = Some((p, p)),
-            }
-        }
-
-        pub fn bounds(&self) -> Option<(Point, Point)> {
-            self.min_max
-        }
-
-        pub fn get(&self, k: Point) -> Option<&T> {
-            self.map.get(&k)
-        }
-
-        pub fn get_entry(&mut self, k: Point) -> Entry<Point, T> {
-            self.map.entry(k)
-        }
-
-        pub fn points_iter(&self) -> impl Iterator<Item = Point> {
-            match self.min_max {
-                Some((min, max)) => points_between(min, max),
-                None => panic!("Can't get an empty iterator presently :("),
-            }
-        }
-    }
-
-    impl<T: fmt::Display> fmt::Display for Grid<T> {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            // See also https://stackoverflow.com/q/30320083#comment92146674_30320443
-            match self.min_max {
-                Some((min, max)) => {
-                    let mut out = String::new();
-                    write!(&mut out, "Min: {} - Max: {}\n", min, max).expect("impossible");
-                    let min_bound = point(min.x-1, min.y-1);
-                    let max_bound = point(max.x+1, max.y+1);
-                    for point in points_between(min_bound, max_bound) {
-                        match self.map.get(&point) {
-                            Some(t) => { out.push_str(&t.to_string()[0..1]); },
-                            None => { out.push(' '); },
-                        }
-                        if point.x == max_bound.x {
-                            out.push('\n');
-                        }
-                    }
-                    write!(f, "{}", out)
-                },
-                None => write!(f, "Empty"),
-            }
-        }
-    }
-
-    #[cfg(test)]
-    mod tests {
-        use std::collections::hash_map::Entry::{Occupied, Vacant};
-        use super::*;
-
-        #[test]
-        fn grid_str() {
-            let mut grid: Grid<u32> = grid();
-            assert_eq!(grid.to_string(), "Empty");
-            grid.insert(point(1, 2), 10);
-            assert_eq!(grid.to_string(), "Min: (1, 2) - Max: (1, 2)\n   \n 1 \n   \n");
-            grid.insert(point(3, 4), 5);
-            assert_eq!(
-                grid.to_string(), "Min: (1, 2) - Max: (3, 4)\n     \n 1   \n     \n   5 \n     \n");
-        }
-
-        #[test]
-        fn grid_points_between() {
-            let mut grid: Grid<u32> = grid();
-            grid.insert(point(2, 2), 10);
-            grid.insert(point(1, 1), 5);
-            let all_points: Vec<_> = grid.points_iter().map(|p| (p, grid.get(p))).collect();
-            let expected = vec!(
-                (point(1, 1), Some(&5)),
-                (point(2, 1), None),
-                (point(1, 2), None),
-                (point(2, 2), Some(&10))
-            );
-            assert_eq!(all_points, expected);
-        }
-
-        #[test]
-        fn grid_entry() {
-            let mut grid: Grid<u32> = grid();
-            let e = grid.get_entry(point(1, 1));
-            let absent = match e {
-                Vacant(_) => true,
-                Occupied(_) => false,
-            };
-            assert!(absent);
-            e.or_insert(10);
-            assert_eq!(grid.get(point(1, 1)), Some(&10));
-        }
-    }
-}
@@ -1,10 +1,42 @@
-use std::fs::File;
+use std::fmt::{Debug, Write};
+use std::fs::{self, File};
+use std::hash::Hash;
 use std::io::{BufRead, BufReader};
+use crate::pathfinding::Graph;
 
-pub fn advent() {
+pub fn advent(args: &[String]) {
     let deps = read_data();
-    println!("Task Order: {}", ordering(&deps));
-    println!("Task Duration: {}", scheduling(&deps, 5, 60));
+    match ordering(&deps) {
+        Ok(order) => println!("Task Order: {}", order),
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        },
+    }
+    let schedule = scheduling(&deps, 5, 60);
+    println!("Task Duration: {}", schedule.makespan());
+    println!("Critical Path: {} ({} seconds)",
+             schedule.critical_path().iter().collect::<String>(), schedule.critical_path_length());
+    println!("Idle Time: {}", schedule.idle_time());
+    println!("Valid Orderings: {}", topo(&deps).count_orderings());
+    println!("{}", schedule.gantt(10));
+
+    let workers = vec![Worker::new(); 5];
+    println!("Makespan by priority:\n{}",
+             priority_report(&compare_priorities(&topo(&deps), &workers, cost_fn(60))));
+
+    if let Some(i) = args.iter().position(|a| a == "--csv") {
+        let path = args.get(i+1).expect("--csv requires a FILE");
+        fs::write(path, schedule.to_csv()).expect("Cannot write");
+        println!("Schedule written to {}", path);
+    }
+
+    if let Some(i) = args.iter().position(|a| a == "--dot") {
+        let path = args.get(i+1).expect("--dot requires a FILE");
+        let reduced = topo(&deps).transitive_reduction().expect("Validated");
+        fs::write(path, reduced.to_dot()).expect("Cannot write");
+        println!("Dependency graph written to {}", path);
+    }
 }
 
 fn read_data() -> Vec<Dep> {
@@ -21,27 +53,39 @@ fn topo(deps: &Vec<Dep>) -> Topology<char> {
     t
 }
 
-fn ordering(deps: &Vec<Dep>) -> String {
-    let mut t = topo(deps);
-    let mut out = String::new();
-    loop {
-        match t.pop() {
-            Some(c) => out.push(c),
-            None => return out,
-        }
-    }
+fn ordering(deps: &Vec<Dep>) -> Result<String, TopologyError<char>> {
+    let t = topo(deps);
+    t.validate()?;
+    Ok(t.topological_sort_by(t.nodes().cloned(), |a, b| a.cmp(b))
+        .expect("Validated")
+        .into_iter().collect())
 }
 
-fn scheduling(deps: &Vec<Dep>, concurrency: u32, modifier: u32) -> u32 {
-    let mut s = Scheduler::new(topo(deps), concurrency,
-                               move |c: char| (c as u32) - ('A' as u32) + 1 + modifier);
-    let mut out = 0;
-    loop {
-        if let None = s.tick() {
-            return out;
-        }
-        out += 1;
+fn cost_fn(modifier: u32) -> impl Clone + Fn(char) -> u32 {
+    move |c: char| (c as u32) - ('A' as u32) + 1 + modifier
+}
+
+fn scheduling(deps: &Vec<Dep>, concurrency: u32, modifier: u32) -> Schedule<char> {
+    Scheduler::new(topo(deps), concurrency, cost_fn(modifier)).run()
+}
+
+// Schedules the same tasks under each priority policy
+fn compare_priorities<T, F>(topo: &Topology<T>, workers: &[Worker<T>], cost_fn: F) -> Vec<(Priority, Schedule<T>)>
+    where T: Clone + Debug + Hash + Ord, F: 'static + Clone + Fn(T) -> u32 {
+    Priority::ALL.iter()
+        .map(|&p| (p, Scheduler::with_workers(topo.clone(), workers.to_vec(), cost_fn.clone()).priority(p).run()))
+        .collect()
+}
+
+// Lists each policy's makespan, marking the best with a *
+fn priority_report<T: Clone + Hash + Ord>(results: &[(Priority, Schedule<T>)]) -> String {
+    let best = results.iter().map(|(_, s)| s.makespan()).min().unwrap_or(0);
+    let mut out = String::new();
+    for (priority, schedule) in results {
+        let marker = if schedule.makespan() == best { " *" } else { "" };
+        writeln!(out, "{:>16}: {}{}", priority, schedule.makespan(), marker).expect("writing to string");
     }
+    out
 }
 
 mod dep {
@@ -85,39 +129,85 @@ mod dep {
 pub use self::dep::Dep;
 
 mod topology {
-    use std::collections::{BTreeMap, HashSet};
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
     use std::collections::btree_map;
+    use std::fmt::{self, Debug, Display, Write};
     use std::hash::Hash;
+    use crate::pathfinding::{Cycle, Edge, Graph};
+
+    // Reasons a set of dependencies can't be ordered
+    #[derive(Debug, Eq, PartialEq)]
+    pub enum TopologyError<T> {
+        SelfDependency(T),
+        // The steps of the cycle in dependency order, e.g. [A, B] if A is required by B and B by A
+        Cycle(Vec<T>),
+    }
+
+    impl<T: Display> Display for TopologyError<T> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                TopologyError::SelfDependency(node) => write!(f, "Step {} depends on itself", node),
+                TopologyError::Cycle(chain) => {
+                    write!(f, "Cycle detected: ")?;
+                    for node in chain {
+                        write!(f, "{} -> ", node)?;
+                    }
+                    write!(f, "{}", chain[0])
+                },
+            }
+        }
+    }
+
+    impl<T: Debug + Display> std::error::Error for TopologyError<T> {}
 
     // TODO remove the Clone constraint
     // See also https://stackoverflow.com/q/32401857/113632
-    #[derive(Debug)]
+    #[derive(Clone, Debug)]
     pub struct Topology<T: Clone + Hash + Ord> {
         deps: BTreeMap<T, HashSet<T>>,
+        // Self-dependencies are not tracked in deps (they'd never be ready), only reported by validate()
+        self_deps: BTreeSet<T>,
     }
 
     impl <T: Clone + Hash + Ord> Topology<T> {
         pub fn new() -> Topology<T> {
-            Topology { deps: BTreeMap::new() }
+            Topology { deps: BTreeMap::new(), self_deps: BTreeSet::new() }
+        }
+
+        // Adds a step with no dependencies (yet)
+        pub fn add(&mut self, node: T) {
+            self.deps.entry(node).or_default();
         }
 
         pub fn register(&mut self, first: T, then: T) {
             if first != then {
                 self.deps.entry(then).or_insert_with(HashSet::new).insert(first.clone());
+            } else {
+                self.self_deps.insert(first.clone());
             }
-            self.deps.entry(first).or_insert_with(HashSet::new);
+            self.add(first);
         }
 
         pub fn is_empty(&self) -> bool {
             self.deps.is_empty()
         }
 
+        pub fn nodes(&self) -> impl Iterator<Item=&T> {
+            self.deps.keys()
+        }
+
+        // The nodes that must be popped before the given node
+        pub fn prerequisites(&self, node: &T) -> impl Iterator<Item=&T> {
+            self.deps.get(node).into_iter().flat_map(|v| v.iter())
+        }
+
         pub fn peek_all(&self) -> impl Iterator<Item=&T> {
             self.deps.iter()
                 .filter(|&(_, v)| v.is_empty())
                 .map(|(k, _)| k)
         }
 
+        #[allow(dead_code)]
         pub fn pop(&mut self) -> Option<T> {
             if self.deps.is_empty() {
                 return None;
@@ -144,6 +234,146 @@ mod topology {
                 _ => panic!("impossible"),
             }
         }
+
+        // Every valid ordering of the steps, in lexicographic order, stopping after limit orderings.
+        // The number of orderings grows factorially, so this is only practical for small graphs.
+        #[allow(dead_code)]
+        pub fn orderings(&self, limit: usize) -> Vec<Vec<T>> {
+            let mut found = Vec::new();
+            Topology::extend_orderings(self, &mut Vec::new(), limit, &mut found);
+            found
+        }
+
+        fn extend_orderings(remaining: &Topology<T>, prefix: &mut Vec<T>, limit: usize, found: &mut Vec<Vec<T>>) {
+            if remaining.is_empty() {
+                found.push(prefix.clone());
+                return;
+            }
+            let ready: Vec<_> = remaining.peek_all().cloned().collect();
+            for node in ready {
+                if found.len() >= limit {
+                    return;
+                }
+                let mut next = remaining.clone();
+                prefix.push(next.pop_exact(&node));
+                Topology::extend_orderings(&next, prefix, limit, found);
+                prefix.pop();
+            }
+        }
+
+        // Counts the valid orderings without listing them, by memoizing on the set of steps already
+        // taken. Supports up to 64 steps, though graphs with few constraints will still be slow.
+        pub fn count_orderings(&self) -> u128 {
+            let nodes: Vec<_> = self.deps.keys().collect();
+            assert!(nodes.len() <= 64, "Too many steps to count: {}", nodes.len());
+            let index: HashMap<_, _> = nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+            let prerequisites: Vec<u64> = nodes.iter()
+                .map(|n| self.deps[*n].iter().fold(0, |mask, p| mask | 1 << index[p]))
+                .collect();
+            let all = if nodes.len() == 64 { u64::MAX } else { (1 << nodes.len()) - 1 };
+            Topology::<T>::count_from(0, all, &prerequisites, &mut HashMap::new())
+        }
+
+        fn count_from(done: u64, all: u64, prerequisites: &[u64], cache: &mut HashMap<u64, u128>) -> u128 {
+            if done == all {
+                return 1;
+            }
+            if let Some(&count) = cache.get(&done) {
+                return count;
+            }
+            let count = (0..prerequisites.len())
+                .filter(|&i| done & 1 << i == 0 && prerequisites[i] & !done == 0)
+                .map(|i| Topology::<T>::count_from(done | 1 << i, all, prerequisites, cache))
+                .sum();
+            cache.insert(done, count);
+            count
+        }
+
+        // All (transitive) prerequisites of each step; only meaningful if the graph is acyclic
+        fn ancestors(&self) -> HashMap<T, HashSet<T>> {
+            fn visit<T: Clone + Hash + Ord>(topo: &Topology<T>, node: &T, found: &mut HashMap<T, HashSet<T>>) {
+                if found.contains_key(node) {
+                    return;
+                }
+                let mut ancestors = HashSet::new();
+                for prerequisite in topo.prerequisites(node) {
+                    visit(topo, prerequisite, found);
+                    ancestors.insert(prerequisite.clone());
+                    ancestors.extend(found[prerequisite].iter().cloned());
+                }
+                found.insert(node.clone(), ancestors);
+            }
+
+            let mut found = HashMap::new();
+            for node in self.nodes() {
+                visit(self, node, &mut found);
+            }
+            found
+        }
+
+        // Renders the graph in graphviz's DOT format, with edges pointing from each step to the steps
+        // that depend on it
+        pub fn to_dot(&self) -> String where T: Display {
+            let mut out = String::from("digraph {\n");
+            for (node, prerequisites) in &self.deps {
+                if prerequisites.is_empty() && !self.deps.values().any(|v| v.contains(node)) {
+                    writeln!(out, "  \"{}\";", node).expect("Cannot write");
+                }
+                let mut prerequisites: Vec<_> = prerequisites.iter().collect();
+                prerequisites.sort();
+                for prerequisite in prerequisites {
+                    writeln!(out, "  \"{}\" -> \"{}\";", prerequisite, node).expect("Cannot write");
+                }
+            }
+            out.push_str("}\n");
+            out
+        }
+    }
+
+    impl <T: Clone + Debug + Hash + Ord> Topology<T> {
+        // Reports self-dependencies, then any cycle, starting the cycle from its smallest step
+        pub fn validate(&self) -> Result<(), TopologyError<T>> {
+            if let Some(node) = self.self_deps.iter().next() {
+                return Err(TopologyError::SelfDependency(node.clone()));
+            }
+            if let Some(Cycle(mut chain)) = self.find_cycle(self.nodes().cloned()) {
+                let start = chain.iter().enumerate().min_by_key(|&(_, n)| n).map(|(i, _)| i).expect("Non-empty");
+                chain.rotate_left(start);
+                return Err(TopologyError::Cycle(chain));
+            }
+            Ok(())
+        }
+
+        // The smallest graph with the same ordering constraints, i.e. without any dependency that is
+        // already implied by another chain of dependencies
+        pub fn transitive_reduction(&self) -> Result<Topology<T>, TopologyError<T>> {
+            self.validate()?;
+            let ancestors = self.ancestors();
+            let mut reduced = Topology::new();
+            for (node, prerequisites) in &self.deps {
+                reduced.add(node.clone());
+                for prerequisite in prerequisites {
+                    let implied = prerequisites.iter().any(|p| ancestors[p].contains(prerequisite));
+                    if !implied {
+                        reduced.register(prerequisite.clone(), node.clone());
+                    }
+                }
+            }
+            Ok(reduced)
+        }
+    }
+
+    // Edges point from each step to the steps that depend on it
+    impl <T: Clone + Debug + Hash + Ord> Graph for Topology<T> {
+        type Node = T;
+        type Cost = u32;
+
+        fn neighbors(&self, source: &T) -> Vec<Edge<T, u32>> {
+            self.deps.iter()
+                .filter(|(_, v)| v.contains(source))
+                .map(|(k, _)| Edge::new(1, source.clone(), k.clone()))
+                .collect()
+        }
     }
 
     #[cfg(test)]
@@ -167,27 +397,372 @@ mod topology {
             assert_eq!(t.pop(), Some('E'));
             assert_eq!(t.pop(), None);
         }
+
+        fn example() -> Topology<char> {
+            let mut t = Topology::new();
+            for (first, then) in [('C', 'A'), ('C', 'F'), ('A', 'B'), ('A', 'D'), ('B', 'E'),
+                                  ('D', 'E'), ('F', 'E')] {
+                t.register(first, then);
+            }
+            t
+        }
+
+        #[test]
+        fn validate() {
+            assert_eq!(example().validate(), Ok(()));
+
+            let mut t = example();
+            t.register('E', 'F');
+            assert_eq!(t.validate(), Err(TopologyError::Cycle(vec!('E', 'F'))));
+
+            let mut t = example();
+            t.register('D', 'D');
+            let error = t.validate().unwrap_err();
+            assert_eq!(error, TopologyError::SelfDependency('D'));
+            assert_eq!(error.to_string(), "Step D depends on itself");
+        }
+
+        #[test]
+        fn orderings() {
+            let t = example();
+            let orderings: Vec<String> = t.orderings(3).iter().map(|o| o.iter().collect()).collect();
+            assert_eq!(orderings, vec!("CABDFE", "CABFDE", "CADBFE"));
+            assert_eq!(t.orderings(100).len(), 8);
+            assert_eq!(t.count_orderings(), 8);
+
+            let mut t = Topology::new();
+            "ABCDEFGHIJ".chars().for_each(|c| t.add(c));
+            assert_eq!(t.count_orderings(), (1..=10).product());
+
+            let mut t = example();
+            t.register('E', 'C');
+            assert!(t.orderings(100).is_empty());
+            assert_eq!(t.count_orderings(), 0);
+        }
+
+        #[test]
+        fn transitive_reduction() {
+            let mut t = example();
+            t.register('C', 'E');
+            t.register('A', 'E');
+            t.register('C', 'B');
+            let reduced = t.transitive_reduction().unwrap();
+            assert_eq!(reduced.to_dot(), example().to_dot());
+
+            t.register('E', 'C');
+            assert!(t.transitive_reduction().is_err());
+        }
+
+        #[test]
+        fn to_dot() {
+            let mut t = Topology::new();
+            t.register("compile", "test");
+            t.register("lint", "test");
+            t.add("docs");
+            assert_eq!(t.to_dot(), "digraph {\n  \"docs\";\n  \"compile\" -> \"test\";\n  \"lint\" -> \"test\";\n}\n");
+        }
     }
 }
-pub use self::topology::Topology;
+pub use self::topology::{Topology, TopologyError};
 
-mod scheduler {
+mod schedule {
     use super::Topology;
     use std::collections::HashMap;
+    use std::fmt::{Display, Write};
+    use std::hash::Hash;
+
+    // A task run by a worker from start until (but not including) end
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub struct Assignment<T> {
+        pub worker: usize,
+        pub task: T,
+        pub start: u32,
+        pub end: u32,
+    }
+
+    impl<T> Assignment<T> {
+        pub fn new(worker: usize, task: T, start: u32, end: u32) -> Assignment<T> {
+            Assignment { worker, task, start, end }
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct Schedule<T> {
+        workers: usize,
+        assignments: Vec<Assignment<T>>,
+        critical_path: Vec<T>,
+    }
+
+    impl<T: Clone + Hash + Ord> Schedule<T> {
+        pub fn new(workers: usize, assignments: Vec<Assignment<T>>, deps: &Topology<T>) -> Schedule<T> {
+            let mut assignments = assignments;
+            assignments.sort_by_key(|a| (a.start, a.worker));
+            let critical_path = Schedule::find_critical_path(&assignments, deps);
+            Schedule { workers, assignments, critical_path }
+        }
+
+        // The longest chain of dependent tasks, which no number of workers could finish faster.
+        // Ties are broken by preferring the earliest tasks.
+        fn find_critical_path(assignments: &[Assignment<T>], deps: &Topology<T>) -> Vec<T> {
+            // (earliest possible finish, prerequisite on the longest chain) for each task; tasks
+            // can't start before their prerequisites finish, so start order is a topological order
+            let mut chains: HashMap<&T, (u32, Option<&T>)> = HashMap::new();
+            for assignment in assignments {
+                let mut prerequisites: Vec<_> = deps.prerequisites(&assignment.task).collect();
+                prerequisites.sort();
+                let longest = prerequisites.into_iter()
+                    .map(|p| (chains.get(p).expect("Prerequisite already scheduled").0, p))
+                    .fold(None, |best: Option<(u32, &T)>, (len, p)| match best {
+                        Some((best_len, _)) if best_len >= len => best,
+                        _ => Some((len, p)),
+                    });
+                let duration = assignment.end - assignment.start;
+                chains.insert(&assignment.task,
+                              (longest.map_or(0, |(len, _)| len) + duration, longest.map(|(_, p)| p)));
+            }
+
+            let mut tasks: Vec<_> = chains.keys().cloned().collect();
+            tasks.sort();
+            let mut cur = tasks.into_iter().fold(None, |best: Option<&T>, t| match best {
+                Some(b) if chains[b].0 >= chains[t].0 => best,
+                _ => Some(t),
+            });
+            let mut path = Vec::new();
+            while let Some(task) = cur {
+                path.push(task.clone());
+                cur = chains[task].1;
+            }
+            path.reverse();
+            path
+        }
+
+        // Ordered by start time, then worker
+        pub fn assignments(&self) -> &[Assignment<T>] {
+            &self.assignments
+        }
+
+        pub fn makespan(&self) -> u32 {
+            self.assignments.iter().map(|a| a.end).max().unwrap_or(0)
+        }
+
+        pub fn critical_path(&self) -> &[T] {
+            &self.critical_path
+        }
+
+        pub fn critical_path_length(&self) -> u32 {
+            self.critical_path.iter()
+                .map(|t| self.assignments.iter().find(|a| a.task == *t).expect("Scheduled"))
+                .map(|a| a.end - a.start)
+                .sum()
+        }
+
+        pub fn for_worker(&self, worker: usize) -> impl Iterator<Item = &Assignment<T>> {
+            self.assignments.iter().filter(move |a| a.worker == worker)
+        }
+
+        // (worker, start, end) for each period a worker has nothing to do before the last task ends
+        pub fn idle_periods(&self) -> Vec<(usize, u32, u32)> {
+            let makespan = self.makespan();
+            let mut idle = Vec::new();
+            for worker in 0..self.workers {
+                let mut time = 0;
+                for assignment in self.for_worker(worker) {
+                    if assignment.start > time {
+                        idle.push((worker, time, assignment.start));
+                    }
+                    time = assignment.end;
+                }
+                if time < makespan {
+                    idle.push((worker, time, makespan));
+                }
+            }
+            idle
+        }
+
+        pub fn idle_time(&self) -> u32 {
+            self.idle_periods().iter().map(|&(_, start, end)| end - start).sum()
+        }
+    }
+
+    impl<T: Clone + Display + Hash + Ord> Schedule<T> {
+        // One row per worker, one column per scale ticks. Each column shows the first character of
+        // the task running at the start of that period, or . if the worker is idle.
+        pub fn gantt(&self, scale: u32) -> String {
+            assert!(scale > 0);
+            let columns = self.makespan().div_ceil(scale);
+            let label_width = (self.workers.max(1) - 1).to_string().len();
+            let mut out = String::new();
+            for worker in 0..self.workers {
+                let assignments: Vec<_> = self.for_worker(worker).collect();
+                let row: String = (0..columns).map(|c| c * scale).map(|time|
+                    assignments.iter().find(|a| a.start <= time && time < a.end)
+                        .and_then(|a| a.task.to_string().chars().next())
+                        .unwrap_or('.'))
+                    .collect();
+                writeln!(out, "{:>w$} |{}|", worker, row, w = label_width).expect("writing to string");
+            }
+            out
+        }
+
+        pub fn to_csv(&self) -> String {
+            let mut out = "worker,task,start,end\n".to_string();
+            for a in self.assignments() {
+                writeln!(out, "{},{},{},{}", a.worker, a.task, a.start, a.end).expect("writing to string");
+            }
+            out
+        }
+    }
+}
+pub use self::schedule::{Assignment, Schedule};
+
+// The puzzle's workers are all identical, but build pipelines' often aren't
+#[allow(dead_code)]
+mod worker {
+    use std::collections::HashSet;
+    use std::hash::Hash;
+
+    // A worker takes cost / speed ticks (rounded up) to finish a task, and may be limited to only
+    // running certain tasks
+    #[derive(Clone, Debug)]
+    pub struct Worker<T: Eq + Hash> {
+        speed: f64,
+        capabilities: Option<HashSet<T>>,
+    }
+
+    impl<T: Eq + Hash> Worker<T> {
+        pub fn new() -> Worker<T> {
+            Worker { speed: 1.0, capabilities: None }
+        }
+
+        pub fn speed(mut self, speed: f64) -> Worker<T> {
+            assert!(speed > 0.0, "Invalid speed {}", speed);
+            self.speed = speed;
+            self
+        }
+
+        pub fn only(mut self, tasks: impl IntoIterator<Item = T>) -> Worker<T> {
+            self.capabilities = Some(tasks.into_iter().collect());
+            self
+        }
+
+        pub fn can_run(&self, task: &T) -> bool {
+            self.capabilities.as_ref().is_none_or(|c| c.contains(task))
+        }
+
+        pub fn duration(&self, cost: u32) -> u32 {
+            ((cost as f64 / self.speed).ceil() as u32).max(1)
+        }
+    }
+
+    impl<T: Eq + Hash> Default for Worker<T> {
+        fn default() -> Self { Worker::new() }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn workers() {
+            let worker = Worker::new().speed(2.0).only(vec!('A', 'B'));
+            assert!(worker.can_run(&'A'));
+            assert!(!worker.can_run(&'C'));
+            assert_eq!(worker.duration(5), 3);
+            assert_eq!(Worker::<char>::new().speed(0.5).duration(5), 10);
+            assert_eq!(Worker::<char>::new().speed(100.0).duration(5), 1);
+        }
+    }
+}
+pub use self::worker::Worker;
+
+mod scheduler {
+    use super::{Assignment, Schedule, Topology, Worker};
+    use std::cmp::Reverse;
+    use std::collections::HashMap;
     use std::fmt;
     use std::hash::Hash;
+    use crate::pathfinding::Graph;
+
+    // Which of the ready tasks are started first, when there aren't enough workers for all of them.
+    // Ties are broken alphabetically (or rather, by the tasks' Ord).
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum Priority {
+        Alphabetical,
+        LongestFirst,
+        MostDependents,
+    }
+
+    impl Priority {
+        pub const ALL: [Priority; 3] = [Priority::Alphabetical, Priority::LongestFirst, Priority::MostDependents];
+    }
+
+    impl fmt::Display for Priority {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.pad(match self {
+                Priority::Alphabetical => "alphabetical",
+                Priority::LongestFirst => "longest first",
+                Priority::MostDependents => "most dependents",
+            })
+        }
+    }
 
     pub struct Scheduler<T: Clone + Hash + Ord> {
         topo: Topology<T>,
-        concurrency: u32,
-        progress: HashMap<T, u32>,
+        // The full topology, since topo is consumed as tasks complete
+        deps: Topology<T>,
+        workers: Vec<Worker<T>>,
+        // Each worker's current task, and the time remaining on it
+        running: Vec<Option<(T, u32)>>,
+        priority: Priority,
+        // Number of tasks that (transitively) depend on each task, for Priority::MostDependents
+        dependents: HashMap<T, u32>,
+        time: u32,
+        assignments: Vec<Assignment<T>>,
         cost_fn: Box<dyn Fn(T) -> u32>,
     }
 
-    impl <T: Clone + Hash + Ord> Scheduler<T> {
+    impl <T: Clone + fmt::Debug + Hash + Ord> Scheduler<T> {
         pub fn new<F>(topo: Topology<T>, concurrency: u32, cost_fn: F) -> Scheduler<T>
             where F: 'static + Fn(T) -> u32 {
-            Scheduler { topo, concurrency, progress: HashMap::new(), cost_fn: Box::new(cost_fn) }
+            Scheduler::with_workers(topo, vec![Worker::new(); concurrency as usize], cost_fn)
+        }
+
+        pub fn with_workers<F>(topo: Topology<T>, workers: Vec<Worker<T>>, cost_fn: F) -> Scheduler<T>
+            where F: 'static + Fn(T) -> u32 {
+            Scheduler {
+                deps: topo.clone(),
+                topo,
+                running: vec![None; workers.len()],
+                workers,
+                priority: Priority::Alphabetical,
+                dependents: HashMap::new(),
+                time: 0,
+                assignments: Vec::new(),
+                cost_fn: Box::new(cost_fn),
+            }
+        }
+
+        pub fn priority(mut self, priority: Priority) -> Scheduler<T> {
+            if priority == Priority::MostDependents && self.dependents.is_empty() {
+                self.dependents = self.deps.nodes()
+                    .map(|n| (n.clone(), self.deps.reachable(vec!(n.clone())).len() as u32 - 1))
+                    .collect();
+            }
+            self.priority = priority;
+            self
+        }
+
+        fn is_running(&self, task: &T) -> bool {
+            self.running.iter().flatten().any(|(t, _)| t == task)
+        }
+
+        // Higher weights are started first
+        fn weight(&self, task: &T) -> u32 {
+            match self.priority {
+                Priority::Alphabetical => 0,
+                Priority::LongestFirst => (self.cost_fn)(task.clone()),
+                Priority::MostDependents => self.dependents[task],
+            }
         }
 
         pub fn tick(&mut self) -> Option<Vec<T>> {
@@ -195,39 +770,60 @@ mod scheduler {
                 return None;
             }
 
-            // add new tasks
-            let idle_workers = self.concurrency as usize - self.progress.len();
-            let new_tasks: Vec<_> = self.topo.peek_all()
-                .filter(|c| !self.progress.contains_key(*c))
-                .take(idle_workers)
+            // add new tasks in priority order, assigning each to the first idle worker able to run it.
+            // Picking the fastest idle worker instead can backfire, by occupying a worker that could
+            // run tasks the others can't, so list preferred workers first.
+            let mut ready: Vec<_> = self.topo.peek_all()
+                .filter(|c| !self.is_running(c))
+                .cloned()
                 .collect();
-            for k in new_tasks {
-                self.progress.insert(k.clone(), (self.cost_fn)(k.clone()));
+            ready.sort_by_cached_key(|t| (Reverse(self.weight(t)), t.clone()));
+            for task in ready {
+                let cost = (self.cost_fn)(task.clone());
+                assert!(cost > 0, "Tasks must take time");
+                let worker = (0..self.workers.len())
+                    .find(|&w| self.running[w].is_none() && self.workers[w].can_run(&task));
+                if let Some(worker) = worker {
+                    let duration = self.workers[worker].duration(cost);
+                    self.assignments.push(Assignment::new(worker, task.clone(), self.time, self.time + duration));
+                    self.running[worker] = Some((task, duration));
+                }
             }
-            assert!(self.progress.len() <= self.concurrency as usize);
+            assert!(self.running.iter().any(|w| w.is_some()),
+                    "No worker can run any of {:?}", self.topo.peek_all().collect::<Vec<_>>());
 
             // pop finished tasks
             let mut done = Vec::new();
-            for (k, v) in self.progress.iter_mut() {
-                *v -= 1;
-                if *v == 0 {
-                    done.push(k.clone());
+            for worker in self.running.iter_mut() {
+                let finished = match worker {
+                    Some((task, remaining)) => {
+                        *remaining -= 1;
+                        if *remaining == 0 { Some(task.clone()) } else { None }
+                    },
+                    None => None,
+                };
+                if let Some(task) = finished {
+                    *worker = None;
+                    done.push(task);
                 }
             }
-            done.iter().for_each(|k|
-                {
-                    self.progress.remove(k);
-                    self.topo.pop_exact(k);
-                });
+            done.iter().for_each(|k| { self.topo.pop_exact(k); });
 
+            self.time += 1;
             Some(done)
         }
+
+        // Runs every task to completion
+        pub fn run(mut self) -> Schedule<T> {
+            while self.tick().is_some() {}
+            Schedule::new(self.workers.len(), self.assignments, &self.deps)
+        }
     }
 
     impl <T: Clone + fmt::Debug + Hash + Ord> fmt::Debug for Scheduler<T> {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "Scheduler {{ topo: {:?}, concurrency: {:?}, progress: {:?} }}",
-                   self.topo, self.concurrency, self.progress)
+            write!(f, "Scheduler {{ topo: {:?}, time: {:?}, priority: {:?}, running: {:?} }}",
+                   self.topo, self.time, self.priority, self.running)
         }
     }
 
@@ -264,9 +860,50 @@ mod scheduler {
             assert_eq!(s.tick(), Some(vec!('E')));
             assert_eq!(s.tick(), None);
         }
+
+        #[test]
+        fn assignments() {
+            let mut t = Topology::new();
+            t.register("compile", "test");
+            t.register("compile", "package");
+            t.register("lint", "package");
+            let costs = |task: &str| match task { "compile" => 5, "lint" => 2, _ => 3 };
+            let schedule = Scheduler::new(t, 2, costs).run();
+            assert_eq!(schedule.assignments(), &[
+                Assignment::new(0, "compile", 0, 5),
+                Assignment::new(1, "lint", 0, 2),
+                Assignment::new(0, "package", 5, 8),
+                Assignment::new(1, "test", 5, 8),
+            ]);
+            assert_eq!(schedule.for_worker(1).count(), 2);
+            assert_eq!(schedule.critical_path(), &["compile", "package"]);
+            assert_eq!(schedule.idle_periods(), vec!((1, 2, 5)));
+        }
+
+        #[test]
+        fn heterogeneous_workers() {
+            let mut t = Topology::new();
+            t.register('A', 'C');
+            t.register('B', 'C');
+            let workers = vec!(Worker::new().only(vec!('A', 'C')), Worker::new().speed(2.0));
+            let schedule = Scheduler::with_workers(t, workers, |c| (c as u32) - ('A' as u32) + 2).run();
+            assert_eq!(schedule.assignments(), &[
+                Assignment::new(0, 'A', 0, 2),
+                Assignment::new(1, 'B', 0, 2),
+                Assignment::new(0, 'C', 2, 6),
+            ]);
+        }
+
+        #[test]
+        #[should_panic(expected = "No worker can run")]
+        fn incapable_workers() {
+            let mut t = Topology::new();
+            t.register('A', 'B');
+            Scheduler::with_workers(t, vec!(Worker::new().only(vec!('B'))), |_| 1).run();
+        }
     }
 }
-pub use self::scheduler::Scheduler;
+pub use self::scheduler::{Priority, Scheduler};
 
 #[cfg(test)]
 mod tests {
@@ -277,12 +914,40 @@ mod tests {
         assert!(read_data().len() > 0);
     }
 
+    #[test]
+    fn priorities() {
+        let mut t = Topology::new();
+        t.add('A');
+        t.add('B');
+        t.register('Z', 'Y');
+        let costs = |c| match c { 'A' | 'B' => 2, 'Z' => 1, _ => 3 };
+        let results = compare_priorities(&t, &vec![Worker::new(); 2], costs);
+        let makespans: Vec<_> = results.iter().map(|(p, s)| (*p, s.makespan())).collect();
+        assert_eq!(makespans, vec!(
+            (Priority::Alphabetical, 6), (Priority::LongestFirst, 6), (Priority::MostDependents, 5)));
+        assert_eq!(priority_report(&results),
+                   "    alphabetical: 6\n   longest first: 6\n most dependents: 5 *\n");
+
+        // Starting the long task first avoids leaving it until the end
+        let mut t = Topology::new();
+        "ABC".chars().for_each(|c| t.add(c));
+        let costs = |c| match c { 'C' => 4, _ => 1 };
+        let results = compare_priorities(&t, &vec![Worker::new(); 2], costs);
+        assert_eq!(results[0].1.makespan(), 5);
+        assert_eq!(results[1].1.makespan(), 4);
+    }
+
     #[test]
     fn example_1() {
         let deps =
             vec!(Dep('C', 'A'), Dep('C', 'F'), Dep('A', 'B'), Dep('A', 'D'), Dep('B', 'E'),
                  Dep('D', 'E'), Dep('F', 'E'));
-        assert_eq!(ordering(&deps), "CABDFE");
+        assert_eq!(ordering(&deps), Ok("CABDFE".into()));
+
+        let deps = vec!(Dep('A', 'B'), Dep('C', 'D'), Dep('D', 'E'), Dep('E', 'C'));
+        let error = ordering(&deps).unwrap_err();
+        assert_eq!(error, TopologyError::Cycle(vec!('C', 'D', 'E')));
+        assert_eq!(error.to_string(), "Cycle detected: C -> D -> E -> C");
     }
 
     #[test]
@@ -290,7 +955,16 @@ mod tests {
         let deps =
             vec!(Dep('C', 'A'), Dep('C', 'F'), Dep('A', 'B'), Dep('A', 'D'), Dep('B', 'E'),
                  Dep('D', 'E'), Dep('F', 'E'));
-        assert_eq!(scheduling(&deps, 2, 0), 15);
+        let schedule = scheduling(&deps, 2, 0);
+        assert_eq!(schedule.makespan(), 15);
+        assert_eq!(schedule.critical_path(), &['C', 'F', 'E']);
+        assert_eq!(schedule.critical_path_length(), 14);
+        assert_eq!(schedule.idle_periods(), vec!((1, 0, 3), (1, 9, 15)));
+        assert_eq!(schedule.idle_time(), 9);
+        assert_eq!(schedule.gantt(1), "0 |CCCABBDDDDEEEEE|\n1 |...FFFFFF......|\n");
+        assert_eq!(schedule.gantt(4), "0 |CBDE|\n1 |.FF.|\n");
+        assert_eq!(schedule.to_csv(),
+                   "worker,task,start,end\n0,C,0,3\n0,A,3,4\n1,F,3,9\n0,B,4,6\n0,D,6,10\n0,E,10,15\n");
     }
 }
 
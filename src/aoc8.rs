@@ -1,10 +1,10 @@
 use std::fs;
-use std::collections::HashMap;
 
 pub fn advent() {
-    let data = read_data();
-    println!("Metadata Sum: {}", metadata_sum(&data));
-    println!("Root Value: {}", metadata_value(&data));
+    let tree = Node::parse(&read_data()).expect("Invalid tree");
+    println!("Nodes: {} Depth: {}", tree.depth_first().count(), tree.depth());
+    println!("Metadata Sum: {}", tree.metadata_sum());
+    println!("Root Value: {}", tree.value());
 }
 
 fn read_data() -> Vec<usize> {
@@ -12,56 +12,307 @@ fn read_data() -> Vec<usize> {
         .trim().split(" ").map(|n| n.parse().unwrap()).collect()
 }
 
+#[cfg(test)]
 fn metadata_sum(data: &[usize]) -> usize {
-    let (end, sum) = metadata_sum_impl(data);
-    assert_eq!(end, data.len());
-    sum
-}
-
-fn metadata_sum_impl(data: &[usize]) -> (usize, usize) {
-    let num_children = data[0];
-    let num_metadata = data[1];
-    let mut next_index = 2;
-    let mut child_sum = 0;
-    for _ in 0..num_children {
-        let (next_node, s) = metadata_sum_impl(&data[next_index..]);
-        next_index += next_node;
-        child_sum += s;
-    }
-    let next_node = next_index + num_metadata;
-    let metadata_sum: usize = data[next_index..next_node].iter().sum();
-    (next_node, child_sum + metadata_sum)
+    Node::parse(data).expect("Invalid tree").metadata_sum()
 }
 
+#[cfg(test)]
 fn metadata_value(data: &[usize]) -> usize {
-    let (end, sum) = metadata_value_impl(data);
-    assert_eq!(end, data.len());
-    sum
+    Node::parse(data).expect("Invalid tree").value()
 }
 
-fn metadata_value_impl(data: &[usize]) -> (usize, usize) {
-    let num_children = data[0];
-    let num_metadata = data[1];
-    let mut next_index = 2;
-    if num_children == 0 {
-        let next_node = next_index + num_metadata;
-        let metadata_sum: usize = data[next_index..next_node].iter().sum();
-        return (next_node, metadata_sum);
+mod node {
+    use std::collections::VecDeque;
+    use std::fmt;
+    use std::mem;
+    use std::str::FromStr;
+    use crate::error::ParseError;
+
+    // The tree is traversed with explicit stacks rather than recursion throughout, so that even very
+    // deeply nested trees can't overflow the call stack. Note the derived impls are still recursive.
+    #[derive(Clone, Debug, Default, Eq, PartialEq)]
+    pub struct Node {
+        children: Vec<Node>,
+        metadata: Vec<usize>,
+    }
+
+    // A node whose children are still being parsed
+    struct Partial {
+        remaining_children: usize,
+        metadata_len: usize,
+        children: Vec<Node>,
+    }
+
+    enum Visit<'a> {
+        Enter(&'a Node),
+        Exit(&'a Node),
+    }
+
+    impl Node {
+        pub fn new(children: Vec<Node>, metadata: Vec<usize>) -> Node {
+            Node { children, metadata }
+        }
+
+        #[allow(dead_code)]
+        pub fn children(&self) -> &[Node] {
+            &self.children
+        }
+
+        #[allow(dead_code)]
+        pub fn metadata(&self) -> &[usize] {
+            &self.metadata
+        }
+
+        // Parses the flat encoding: a header of the number of children and the number of metadata
+        // entries, then the children, then the metadata
+        pub fn parse(data: &[usize]) -> Result<Node, ParseError> {
+            let truncated = |expected: &str, index: usize|
+                ParseError::Malformed(format!("tree, truncated while reading {} at index {}", expected, index));
+            let read_header = |index: usize| match data.get(index..index + 2) {
+                Some(header) => Ok(Partial { remaining_children: header[0], metadata_len: header[1], children: Vec::new() }),
+                None => Err(truncated("header", index)),
+            };
+
+            let mut index = 0;
+            let mut stack = vec!(read_header(index)?);
+            index += 2;
+            while let Some(partial) = stack.last_mut() {
+                if partial.remaining_children > 0 {
+                    partial.remaining_children -= 1;
+                    stack.push(read_header(index)?);
+                    index += 2;
+                    continue;
+                }
+
+                let partial = stack.pop().expect("Non-empty");
+                // The length is untrusted input, so it could be large enough to overflow
+                let metadata = data.get(index..).and_then(|d| d.get(..partial.metadata_len))
+                    .ok_or_else(|| truncated("metadata", index))?;
+                index += partial.metadata_len;
+                let node = Node::new(partial.children, metadata.to_vec());
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None if index < data.len() =>
+                        return Err(ParseError::Malformed(format!("tree, trailing data at index {}", index))),
+                    None => return Ok(node),
+                }
+            }
+            unreachable!("Returns once the root is complete")
+        }
+
+        // The inverse of parse()
+        #[allow(dead_code)]
+        pub fn serialize(&self) -> Vec<usize> {
+            let mut out = Vec::new();
+            let mut stack = vec!(Visit::Enter(self));
+            while let Some(visit) = stack.pop() {
+                match visit {
+                    Visit::Enter(node) => {
+                        out.push(node.children.len());
+                        out.push(node.metadata.len());
+                        stack.push(Visit::Exit(node));
+                        stack.extend(node.children.iter().rev().map(Visit::Enter));
+                    },
+                    Visit::Exit(node) => out.extend_from_slice(&node.metadata),
+                }
+            }
+            out
+        }
+
+        // Pre-order, i.e. each node before its children
+        pub fn depth_first(&self) -> DepthFirst<'_> {
+            DepthFirst { stack: vec!(self) }
+        }
+
+        #[allow(dead_code)]
+        pub fn breadth_first(&self) -> BreadthFirst<'_> {
+            BreadthFirst { queue: vec!(self).into() }
+        }
+
+        pub fn metadata_sum(&self) -> usize {
+            self.depth_first().flat_map(|n| n.metadata.iter()).sum()
+        }
+
+        // A leaf's value is the sum of its metadata, otherwise each metadata entry is a (1-based)
+        // index of a child whose value is included
+        pub fn value(&self) -> usize {
+            // Values of the children of every node on the stack, in order
+            let mut values = Vec::new();
+            let mut stack = vec!(Visit::Enter(self));
+            while let Some(visit) = stack.pop() {
+                match visit {
+                    Visit::Enter(node) => {
+                        stack.push(Visit::Exit(node));
+                        stack.extend(node.children.iter().rev().map(Visit::Enter));
+                    },
+                    Visit::Exit(node) => {
+                        let child_values = values.split_off(values.len() - node.children.len());
+                        let value = if node.children.is_empty() {
+                            node.metadata.iter().sum()
+                        } else {
+                            node.metadata.iter()
+                                .filter_map(|&i| i.checked_sub(1).and_then(|i| child_values.get(i)))
+                                .sum()
+                        };
+                        values.push(value);
+                    },
+                }
+            }
+            values.pop().expect("Root value")
+        }
+
+        pub fn depth(&self) -> usize {
+            let mut max_depth = 0;
+            let mut stack = vec!((self, 1));
+            while let Some((node, depth)) = stack.pop() {
+                max_depth = max_depth.max(depth);
+                stack.extend(node.children.iter().map(|c| (c, depth + 1)));
+            }
+            max_depth
+        }
+    }
+
+    // Dropping the children recursively could overflow the stack, so they're flattened out first
+    impl Drop for Node {
+        fn drop(&mut self) {
+            let mut stack = mem::take(&mut self.children);
+            while let Some(mut node) = stack.pop() {
+                stack.append(&mut node.children);
+            }
+        }
+    }
+
+    pub struct DepthFirst<'a> {
+        stack: Vec<&'a Node>,
+    }
+
+    impl<'a> Iterator for DepthFirst<'a> {
+        type Item = &'a Node;
+
+        fn next(&mut self) -> Option<&'a Node> {
+            let node = self.stack.pop()?;
+            self.stack.extend(node.children.iter().rev());
+            Some(node)
+        }
+    }
+
+    pub struct BreadthFirst<'a> {
+        queue: VecDeque<&'a Node>,
+    }
+
+    impl<'a> Iterator for BreadthFirst<'a> {
+        type Item = &'a Node;
+
+        fn next(&mut self) -> Option<&'a Node> {
+            let node = self.queue.pop_front()?;
+            self.queue.extend(node.children.iter());
+            Some(node)
+        }
+    }
+
+    impl FromStr for Node {
+        type Err = ParseError;
+
+        fn from_str(s: &str) -> Result<Self, ParseError> {
+            let data = s.split_whitespace().map(|n| n.parse()).collect::<Result<Vec<_>, _>>()?;
+            Node::parse(&data)
+        }
     }
 
-    let mut child_values = HashMap::new();
-    for i in 0..num_children {
-        let (next_node, v) = metadata_value_impl(&data[next_index..]);
-        next_index += next_node;
-        child_values.insert(i+1, v);
+    // Draws the tree with one node per line, e.g.
+    //   [1, 1, 2]
+    //   ├── [10, 11, 12]
+    //   └── [2]
+    //       └── [99]
+    impl fmt::Display for Node {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            // Each node is paired with the prefix for its line and the prefix for its children's lines
+            let mut stack = vec!((self, String::new(), String::new()));
+            while let Some((node, prefix, child_prefix)) = stack.pop() {
+                writeln!(f, "{}{:?}", prefix, node.metadata)?;
+                for (i, child) in node.children.iter().enumerate().rev() {
+                    let last = i + 1 == node.children.len();
+                    let (branch, indent) = if last { ("└── ", "    ") } else { ("├── ", "│   ") };
+                    stack.push((child, format!("{}{}", child_prefix, branch), format!("{}{}", child_prefix, indent)));
+                }
+            }
+            Ok(())
+        }
     }
-    let mut value_sum: usize = 0;
-    for i in 0..num_metadata {
-        value_sum += child_values.get(&data[next_index + i]).unwrap_or(&0);
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const EXAMPLE: &[usize] = &[2, 3, 0, 3, 10, 11, 12, 1, 1, 0, 1, 99, 2, 1, 1, 2];
+
+        fn example() -> Node {
+            Node::new(vec!(
+                Node::new(vec!(), vec!(10, 11, 12)),
+                Node::new(vec!(Node::new(vec!(), vec!(99))), vec!(2))),
+                vec!(1, 1, 2))
+        }
+
+        #[test]
+        fn parse() {
+            assert_eq!(Node::parse(EXAMPLE), Ok(example()));
+            assert_eq!("0 1 99".parse(), Ok(Node::new(vec!(), vec!(99))));
+            assert_eq!(Node::parse(&[]), Err(ParseError::Malformed("tree, truncated while reading header at index 0".into())));
+            assert_eq!(Node::parse(&[1, 1, 0]), Err(ParseError::Malformed("tree, truncated while reading header at index 2".into())));
+            assert_eq!(Node::parse(&[1, 1, 0, 1, 99]), Err(ParseError::Malformed("tree, truncated while reading metadata at index 5".into())));
+            assert_eq!(Node::parse(&[0, usize::MAX]), Err(ParseError::Malformed("tree, truncated while reading metadata at index 2".into())));
+            assert_eq!(Node::parse(&[1, 1, 0, usize::MAX, 7]), Err(ParseError::Malformed("tree, truncated while reading metadata at index 4".into())));
+            assert!("0 18446744073709551615".parse::<Node>().is_err());
+            assert_eq!(Node::parse(&[0, 1, 99, 4]), Err(ParseError::Malformed("tree, trailing data at index 3".into())));
+            assert!("0 1 x".parse::<Node>().is_err());
+        }
+
+        #[test]
+        fn round_trip() {
+            assert_eq!(example().serialize(), EXAMPLE);
+            assert_eq!(Node::default().serialize(), vec!(0, 0));
+        }
+
+        #[test]
+        fn traversal() {
+            let tree = example();
+            let metadata = |nodes: Vec<&Node>| nodes.into_iter().map(|n| n.metadata()[0]).collect::<Vec<_>>();
+            assert_eq!(metadata(tree.depth_first().collect()), vec!(1, 10, 2, 99));
+            assert_eq!(metadata(tree.breadth_first().collect()), vec!(1, 10, 2, 99));
+
+            let tree = Node::new(vec!(example(), Node::new(vec!(), vec!(7))), vec!(0));
+            assert_eq!(metadata(tree.depth_first().collect()), vec!(0, 1, 10, 2, 99, 7));
+            assert_eq!(metadata(tree.breadth_first().collect()), vec!(0, 1, 7, 10, 2, 99));
+            assert_eq!(tree.depth(), 4);
+        }
+
+        #[test]
+        fn pretty_print() {
+            assert_eq!(example().to_string(),
+                       "[1, 1, 2]\n├── [10, 11, 12]\n└── [2]\n    └── [99]\n");
+            let tree = Node::new(vec!(example(), Node::new(vec!(), vec!(7))), vec!(0));
+            assert_eq!(tree.to_string(),
+                       "[0]\n├── [1, 1, 2]\n│   ├── [10, 11, 12]\n│   └── [2]\n│       └── [99]\n└── [7]\n");
+        }
+
+        #[test]
+        fn deeply_nested() {
+            // Each node has one child and one metadata entry, which is far deeper than recursion allows
+            const DEPTH: usize = 1_000_000;
+            let mut data = [1, 1].repeat(DEPTH - 1);
+            data.extend(&[0, 1]);
+            data.extend(vec!(1; DEPTH));
+
+            let tree = Node::parse(&data).unwrap();
+            assert_eq!(tree.depth(), DEPTH);
+            assert_eq!(tree.metadata_sum(), DEPTH);
+            assert_eq!(tree.value(), 1);
+            assert_eq!(tree.serialize(), data);
+        }
     }
-    let next_node = next_index + num_metadata;
-    (next_node, value_sum)
 }
+pub use self::node::Node;
 
 #[cfg(test)]
 mod tests {
@@ -1,13 +1,63 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::Write;
+use std::fs;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use regex::Regex;
+use crate::error::ParseError;
+
+const REAL_DATA: &str = "data/day9.txt";
 
 pub fn advent(args: &[String]) {
-    println!("High Score: {}", simulate(432, 71019));
+    let game = read_data();
+    if args.iter().any(|a| a == "--bench") {
+        // Manual microbenchmarking with --release suggests ~10000 is optimal for this many marbles
+        let max_capacity: usize = match args.iter().position(|a| a == "--capacity") {
+            Some(i) => args.get(i+1).expect("--capacity requires a number").parse().expect("Invalid capacity"),
+            None => 10000,
+        };
+        benchmark(game, max_capacity);
+        return;
+    }
+
+    let outcome = play(game, Rules::default(), &mut DequeBacked::new());
+    match outcome.winner() {
+        Some(winner) => println!("High Score: {} (player {})", outcome.high_score(), winner),
+        None => println!("High Score: 0"),
+    }
+    println!("Lead Changes: {}", outcome.lead_changes.len());
+    let game = Game { last_marble: game.last_marble * 100, ..game };
+    println!("High Score x100: {}", play(game, Rules::default(), &mut DequeBacked::new()).high_score());
+}
+
+fn read_data() -> Game {
+    fs::read_to_string(REAL_DATA).expect("Cannot open").trim().parse().expect("Invalid game")
+}
 
-    // Manual microbenchmarking with --release suggests ~10000 is optimal for this many marbles
-    let max_capacity: usize = args.get(0).unwrap_or(&"10000".to_string()).parse().unwrap();
-    println!("High Score x100 with capacity {}: {}", max_capacity,
-             simulate_generic(432, 71019 * 100, &mut MultiVecBacked::for_capacity(max_capacity) ));
+fn benchmark(game: Game, max_capacity: usize) {
+    if cfg!(debug_assertions) {
+        println!("WARNING: without --release these timings aren't representative");
+    }
+    let large_game = Game { last_marble: game.last_marble * 100, ..game };
+    println!("{:>14} {:>16} {:>16}", "Backend",
+             format!("{} marbles", game.last_marble), format!("{} marbles", large_game.last_marble));
+    // The O(n) backends would take far too long for the larger game
+    let start = Instant::now();
+    simulate(game.players, game.last_marble);
+    println!("{:>14} {:>16?} {:>16}", "simulate()", start.elapsed(), "-");
+    println!("{:>14} {:>16?} {:>16}", "VecBacked", time_backend(game, VecBacked::new), "-");
+    let multi_vec = || MultiVecBacked::for_capacity(max_capacity);
+    println!("{:>14} {:>16?} {:>16?}", "MultiVecBacked",
+             time_backend(game, multi_vec), time_backend(large_game, multi_vec));
+    println!("{:>14} {:>16?} {:>16?}", "DequeBacked",
+             time_backend(game, DequeBacked::new), time_backend(large_game, DequeBacked::new));
+}
+
+fn time_backend<R: RingBuf>(game: Game, new: impl Fn() -> R) -> Duration {
+    let mut ring = new();
+    let start = Instant::now();
+    play(game, Rules::default(), &mut ring);
+    start.elapsed()
 }
 
 fn simulate(players: u32, high_marble: u32) -> u32 {
@@ -44,34 +94,116 @@ trait RingBuf {
     fn insert(&mut self, index: usize, value: u32);
     fn remove(&mut self, index: usize) -> u32;
     fn clear(&mut self);
+    fn to_vec(&self) -> Vec<u32>;
 }
 
-fn simulate_generic<R>(players: u32, high_marble: u32, ring: &mut R) -> u32 where R: RingBuf {
-    let mut scores: HashMap<_, _> = {1..players+1}.map(|n| (n, 0)).collect();
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Game { players: u32, last_marble: u32 }
+
+impl FromStr for Game {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"^(\d+) players; last marble is worth (\d+) points$").unwrap();
+        }
+
+        let caps = regex_captures!(RE, s)?;
+        let players = capture_group!(caps, 1).parse()?;
+        if players == 0 {
+            return Err(ParseError::Malformed(format!("game, no players in '{}'", s)));
+        }
+        let last_marble = capture_group!(caps, 2).parse()?;
+        Ok(Game { players, last_marble })
+    }
+}
+
+// The placement and scoring rules, the defaults are the puzzle's: every 23rd marble scores itself
+// and the marble 7 counterclockwise of the current one, and other marbles are placed 2 clockwise
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Rules { scoring_multiple: u32, removal_offset: usize, insertion_step: usize }
+impl Rules {
+    #[allow(dead_code)]
+    fn scoring_multiple(mut self, scoring_multiple: u32) -> Self {
+        assert!(scoring_multiple > 0, "Scoring multiple must be positive");
+        self.scoring_multiple = scoring_multiple;
+        self
+    }
+
+    #[allow(dead_code)]
+    fn removal_offset(mut self, removal_offset: usize) -> Self {
+        self.removal_offset = removal_offset;
+        self
+    }
+
+    #[allow(dead_code)]
+    fn insertion_step(mut self, insertion_step: usize) -> Self {
+        self.insertion_step = insertion_step;
+        self
+    }
+}
+impl Default for Rules {
+    fn default() -> Self { Rules { scoring_multiple: 23, removal_offset: 7, insertion_step: 2 } }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct LeadChange { player: u32, turn: u32, score: u32 }
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Outcome {
+    // Indexed by player - 1, players are numbered from 1
+    scores: Vec<u32>,
+    // Every time a player took the (outright) lead, ties don't change the leader
+    lead_changes: Vec<LeadChange>,
+    // The final ring and the index of the current marble in it
+    ring: Vec<u32>,
+    current: usize,
+}
+impl Outcome {
+    fn high_score(&self) -> u32 { self.scores.iter().copied().max().unwrap_or(0) }
+    fn winner(&self) -> Option<u32> { self.lead_changes.last().map(|c| c.player) }
+}
+
+// Marble n is played on turn n, by player (n - 1) % players + 1
+fn play<R: RingBuf>(game: Game, rules: Rules, ring: &mut R) -> Outcome {
+    let mut scores = vec!(0; game.players as usize);
+    let mut lead_changes: Vec<LeadChange> = Vec::new();
     let mut cur_pos = 0;
+    ring.clear();
     ring.insert(0, 0);
 
-    //print_ring(0, cur_pos, &ring);
-    for marble in 1..high_marble+1 {
-        let player = marble % players;
-        if marble % 23 == 0 {
-            let seven_counterclockwise_of_cur = (cur_pos + ring.len() - 8) % (ring.len()) + 1; // NEGATIVES???
-            let removed_marble = ring.remove(seven_counterclockwise_of_cur);
-            cur_pos = seven_counterclockwise_of_cur;
-            let score = marble + removed_marble;
-            scores.entry(player).and_modify(|s| *s += score).or_insert(score);
-        } else {
-            let two_clockwise_of_cur = (cur_pos + 1) % (ring.len()) + 1;
-            cur_pos = two_clockwise_of_cur;
-            if cur_pos == 0 {
-                cur_pos = ring.len();
+    for marble in 1..=game.last_marble {
+        let player = (marble - 1) % game.players + 1;
+        let len = ring.len() as isize;
+        if marble % rules.scoring_multiple == 0 {
+            // If every marble has been removed there's nothing to score but the marble itself
+            let mut score = marble;
+            if len > 0 {
+                let index = (cur_pos as isize - rules.removal_offset as isize).rem_euclid(len) as usize;
+                score += ring.remove(index);
+                cur_pos = if ring.len() > 0 { index % ring.len() } else { 0 };
+            }
+            let total = &mut scores[player as usize - 1];
+            *total += score;
+            let high_score = lead_changes.last().map(|c| c.score).unwrap_or(0);
+            if *total > high_score {
+                match lead_changes.last_mut() {
+                    Some(leader) if leader.player == player => { leader.score = *total; },
+                    _ => lead_changes.push(LeadChange { player, turn: marble, score: *total }),
+                }
             }
+        } else {
+            cur_pos = if len > 0 { (cur_pos as isize + rules.insertion_step as isize - 1).rem_euclid(len) as usize + 1 } else { 0 };
             ring.insert(cur_pos, marble);
         }
-        //print_ring(player, cur_pos, &ring);
     }
 
-    *scores.values().max().expect("expected at least one player")
+    Outcome { scores, lead_changes, ring: ring.to_vec(), current: cur_pos }
+}
+
+#[cfg(test)]
+fn simulate_generic<R>(players: u32, high_marble: u32, ring: &mut R) -> u32 where R: RingBuf {
+    play(Game { players, last_marble: high_marble }, Rules::default(), ring).high_score()
 }
 
 // Just a wrapper around a Vec, essentially the same behavior as simulate()
@@ -86,6 +218,7 @@ impl RingBuf for VecBacked {
     fn insert(&mut self, index: usize, value: u32) { self.vec.insert(index, value); }
     fn remove(&mut self, index: usize) -> u32 { self.vec.remove(index) }
     fn clear(&mut self) { self.vec.clear(); }
+    fn to_vec(&self) -> Vec<u32> { self.vec.clone() }
 }
 
 // A sequence of vecs to support more efficient insertions; should be O(n) on the number of inner
@@ -157,8 +290,66 @@ impl RingBuf for MultiVecBacked {
         self.vecs.clear();
         self.vecs.push(Vec::with_capacity(self.max_capacity));
     }
+
+    fn to_vec(&self) -> Vec<u32> { self.vecs.concat() }
 }
 
+// A VecDeque that's rotated so that the cursor (the most recently accessed position) is always at
+// the back, making insertions and removals next to it O(1). The logical index 0 is tracked
+// separately, so any index can still be accessed in O(distance from the cursor) - for the game
+// that distance is at most 7, so every operation is effectively constant-time.
+struct DequeBacked { deque: VecDeque<u32>, start: usize }
+impl DequeBacked {
+    fn new() -> Self { DequeBacked { deque: VecDeque::new(), start: 0 } }
+
+    // Moves the cursor by the given number of steps (positive is clockwise, i.e. towards higher
+    // indices) so that the element now at the back of the deque was the steps'th one after it
+    fn rotate(&mut self, steps: isize) {
+        let len = self.deque.len();
+        if len == 0 {
+            return;
+        }
+        let steps = steps.rem_euclid(len as isize) as usize;
+        // rotate_left/right are O(min(steps, len - steps)) already
+        self.deque.rotate_left(steps);
+        self.start = (self.start + len - steps) % len;
+    }
+
+    // Rotates so that the given logical index is the first element, i.e. just after the cursor
+    fn seek(&mut self, index: usize) {
+        let len = self.deque.len();
+        if len > 0 {
+            let position = (self.start + index) % len;
+            // Choose the shorter direction to rotate in
+            self.rotate(if position <= len / 2 { position as isize } else { position as isize - len as isize });
+        }
+    }
+}
+impl RingBuf for DequeBacked {
+    fn len(&self) -> usize { self.deque.len() }
+    fn insert(&mut self, index: usize, value: u32) {
+        // Indices would otherwise wrap around the ring, unlike the other RingBufs
+        assert!(index <= self.len(), "insertion index (is {}) should be <= len (is {})", index, self.len());
+        self.seek(index);
+        self.deque.push_back(value);
+        // The elements before index now follow the inserted element
+        self.start = (self.deque.len() - 1 - index) % self.deque.len();
+    }
+    fn remove(&mut self, index: usize) -> u32 {
+        assert!(index < self.len(), "removal index (is {}) should be < len (is {})", index, self.len());
+        self.seek(index + 1);
+        let value = self.deque.pop_back().expect("Non-empty");
+        self.start = if self.deque.is_empty() { 0 } else { (self.deque.len() - index) % self.deque.len() };
+        value
+    }
+    fn clear(&mut self) {
+        self.deque.clear();
+        self.start = 0;
+    }
+    fn to_vec(&self) -> Vec<u32> {
+        self.deque.iter().cycle().skip(self.start).take(self.deque.len()).copied().collect()
+    }
+}
 
 #[allow(dead_code)]
 fn print_ring(player: u32, cur_pos: usize, ring: &Vec<u32>) {
@@ -199,6 +390,110 @@ mod tests {
         examples_generic(&mut MultiVecBacked::new());
     }
 
+    #[test]
+    fn examples_dequebacked() {
+        examples_generic(&mut DequeBacked::new());
+    }
+
+    #[test]
+    fn dequebacked_indices() {
+        // Should behave exactly like a Vec, regardless of where the cursor is
+        let mut ring = DequeBacked::new();
+        let mut vec = Vec::new();
+        for i in 0..50 {
+            let index = (i * 7) % (vec.len() + 1);
+            ring.insert(index, i as u32);
+            vec.insert(index, i as u32);
+            if i % 3 == 0 {
+                let index = (i * 11) % vec.len();
+                assert_eq!(ring.remove(index), vec.remove(index));
+            }
+            assert_eq!(ring.len(), vec.len());
+            let start = ring.start;
+            let contents: Vec<_> = (0..ring.len()).map(|i| ring.deque[(start + i) % ring.len()]).collect();
+            assert_eq!(contents, vec);
+        }
+        ring.rotate(-3);
+        assert_eq!(ring.remove(0), vec[0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "removal index (is 5) should be < len (is 3)")]
+    fn dequebacked_remove_out_of_bounds() {
+        let mut ring = DequeBacked::new();
+        for i in 0..3 {
+            ring.insert(i, i as u32);
+        }
+        ring.remove(5);
+    }
+
+    #[test]
+    #[should_panic(expected = "insertion index (is 4) should be <= len (is 3)")]
+    fn dequebacked_insert_out_of_bounds() {
+        let mut ring = DequeBacked::new();
+        for i in 0..3 {
+            ring.insert(i, i as u32);
+        }
+        ring.insert(4, 4);
+    }
+
+    #[test]
+    fn large_game() {
+        assert_eq!(simulate_generic(10, 1618 * 100, &mut DequeBacked::new()),
+                   simulate_generic(10, 1618 * 100, &mut MultiVecBacked::new()));
+    }
+
+    #[test]
+    fn parse_game() {
+        assert_eq!("10 players; last marble is worth 1618 points".parse(), Ok(Game { players: 10, last_marble: 1618 }));
+        assert!("0 players; last marble is worth 1618 points".parse::<Game>().is_err());
+        assert!("10 players".parse::<Game>().is_err());
+        assert_eq!(read_data(), Game { players: 432, last_marble: 71019 });
+    }
+
+    #[test]
+    fn outcome() {
+        let outcome = play(Game { players: 9, last_marble: 25 }, Rules::default(), &mut DequeBacked::new());
+        assert_eq!(outcome.scores, [0, 0, 0, 0, 32, 0, 0, 0, 0]);
+        assert_eq!(outcome.lead_changes, [LeadChange { player: 5, turn: 23, score: 32 }]);
+        assert_eq!(outcome.winner(), Some(5));
+        assert_eq!(outcome.ring, [0, 16, 8, 17, 4, 18, 19, 2, 24, 20, 25, 10, 21, 5, 22, 11, 1, 12, 6, 13, 3, 14, 7, 15]);
+        assert_eq!(outcome.current, 10);
+    }
+
+    #[test]
+    fn custom_rules() {
+        let rules = Rules::default().scoring_multiple(5).removal_offset(2).insertion_step(1);
+        let outcome = play(Game { players: 2, last_marble: 6 }, rules, &mut VecBacked::new());
+        assert_eq!(outcome.scores, [7, 0]);
+        assert_eq!(outcome.ring, [0, 1, 3, 6, 4]);
+        assert_eq!(outcome.current, 3);
+
+        let rules = Rules::default().scoring_multiple(2).removal_offset(1).insertion_step(1);
+        let outcome = play(Game { players: 3, last_marble: 6 }, rules, &mut VecBacked::new());
+        assert_eq!(outcome.scores, [5, 2, 9]);
+        assert_eq!(outcome.lead_changes, [
+            LeadChange { player: 2, turn: 2, score: 2 },
+            LeadChange { player: 1, turn: 4, score: 5 },
+            LeadChange { player: 3, turn: 6, score: 9 }]);
+        assert_eq!(outcome.ring, [5]);
+
+        // Every marble scores, so the ring empties out
+        let rules = Rules::default().scoring_multiple(1);
+        let outcome = play(Game { players: 2, last_marble: 4 }, rules, &mut DequeBacked::new());
+        assert_eq!(outcome.scores, [1 + 3, 2 + 4]);
+        assert_eq!(outcome.ring, []);
+    }
+
+    #[test]
+    fn backends_agree() {
+        let game = Game { players: 13, last_marble: 7999 };
+        let rules = Rules::default().scoring_multiple(11).removal_offset(30).insertion_step(5);
+        let outcome = play(game, rules, &mut VecBacked::new());
+        assert_eq!(play(game, rules, &mut MultiVecBacked::for_capacity(100)), outcome);
+        assert_eq!(play(game, rules, &mut DequeBacked::new()), outcome);
+    }
+
     // would be preferable to pass in a Fn() -> RingBuf or similar, but I haven't figured out how
     fn examples_generic<T>(ring: &mut T) where T: RingBuf {
         ring.clear();
@@ -0,0 +1,1258 @@
+// Cellular automata on a line of cells, generalizing day 12's pots, or on a plane, generalizing day
+// 18's lumber collection. Each cell is in one of k states, where state 0 is the empty background that
+// extends infinitely in every direction, and each generation a cell's next state is determined by
+// the cells within a fixed radius of it.
+//   https://en.wikipedia.org/wiki/Elementary_cellular_automaton
+//   https://en.wikipedia.org/wiki/Conway%27s_Game_of_Life
+use crate::error::ParseError;
+
+// The characters used to represent each state, following day 12's format for two-state automata
+const SYMBOLS: [char; 10] = ['.', '#', '2', '3', '4', '5', '6', '7', '8', '9'];
+
+pub fn symbol(state: u8) -> char {
+    SYMBOLS[state as usize]
+}
+
+pub fn parse_states(s: &str) -> Result<Vec<u8>, ParseError> {
+    s.chars()
+        .map(|c| SYMBOLS.iter().position(|&x| x == c).map(|i| i as u8)
+            .ok_or_else(|| ParseError::Malformed(format!("state: {:?}", c))))
+        .collect()
+}
+
+mod rule {
+    use super::parse_states;
+    use crate::error::ParseError;
+
+    // A lookup table from every possible window of 2*radius+1 cells to the next state of the center
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub struct Rule1D {
+        radius: usize,
+        states: u8,
+        table: Vec<u8>,
+    }
+
+    impl Rule1D {
+        // A rule that empties every cell, to be filled in with .rule()
+        pub fn new(radius: usize, states: u8) -> Rule1D {
+            assert!((2..=10).contains(&states), "Unsupported number of states: {}", states);
+            let size = (states as usize).checked_pow(2 * radius as u32 + 1)
+                .filter(|&size| size <= 1 << 24)
+                .unwrap_or_else(|| panic!("Too many windows for radius {} and {} states", radius, states));
+            Rule1D { radius, states, table: vec![0; size] }
+        }
+
+        // The elementary (radius 1, two-state) automaton with the given Wolfram code, where bit n
+        // of the number is the next state for the window whose cells spell n in binary
+        #[allow(dead_code)]
+        pub fn wolfram(number: u8) -> Rule1D {
+            let mut rule = Rule1D::new(1, 2);
+            for (i, next) in rule.table.iter_mut().enumerate() {
+                *next = (number >> i) & 1;
+            }
+            rule
+        }
+
+        // Parses day 12's format, e.g. "..#.# => #", one window per line. Windows that aren't listed
+        // become empty; the radius is inferred from the window length and the number of states
+        // from the largest state used.
+        pub fn parse(lines: &[impl AsRef<str>]) -> Result<Rule1D, ParseError> {
+            let mut parsed = Vec::new();
+            for line in lines {
+                let line = line.as_ref();
+                let parts: Vec<_> = line.split(" => ").collect();
+                if parts.len() != 2 {
+                    return Err(ParseError::Malformed(format!("rule: {}", line)));
+                }
+                let window = parse_states(parts[0])?;
+                let next = parse_states(parts[1])?;
+                if window.len() % 2 == 0 || next.len() != 1 {
+                    return Err(ParseError::Malformed(format!("rule: {}", line)));
+                }
+                parsed.push((window, next[0]));
+            }
+
+            let width = parsed.first().map(|(w, _)| w.len()).ok_or("rule: no windows")?;
+            if parsed.iter().any(|(w, _)| w.len() != width) {
+                return Err(ParseError::Malformed("rule: inconsistent window sizes".into()));
+            }
+            let states = parsed.iter()
+                .flat_map(|(w, n)| w.iter().chain(std::iter::once(n)))
+                .max().map_or(2, |&s| std::cmp::max(2, s + 1));
+
+            let mut rule = Rule1D::new(width / 2, states);
+            for (window, next) in parsed {
+                rule = rule.rule(&window, next);
+            }
+            Ok(rule)
+        }
+
+        pub fn rule(mut self, window: &[u8], next: u8) -> Rule1D {
+            assert!(next < self.states, "Invalid state: {}", next);
+            let index = self.index(window);
+            self.table[index] = next;
+            self
+        }
+
+        pub fn radius(&self) -> usize {
+            self.radius
+        }
+
+        pub fn states(&self) -> u8 {
+            self.states
+        }
+
+        pub fn next(&self, window: &[u8]) -> u8 {
+            self.table[self.index(window)]
+        }
+
+        // Whether an empty window stays empty, which is necessary for the background to stay empty
+        pub fn is_quiescent(&self) -> bool {
+            self.table[0] == 0
+        }
+
+        // Treats the window as a base-k number, most significant cell first
+        fn index(&self, window: &[u8]) -> usize {
+            assert_eq!(window.len(), 2 * self.radius + 1, "Window doesn't match radius {}", self.radius);
+            window.iter().fold(0, |index, &cell| {
+                assert!(cell < self.states, "Invalid state: {}", cell);
+                index * self.states as usize + cell as usize
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn wolfram() {
+            let rule = Rule1D::wolfram(90);
+            let next: Vec<_> = [[1, 1, 1], [1, 1, 0], [1, 0, 1], [1, 0, 0], [0, 1, 1], [0, 1, 0], [0, 0, 1], [0, 0, 0]]
+                .iter().map(|w| rule.next(w)).collect();
+            assert_eq!(next, vec!(0, 1, 0, 1, 1, 0, 1, 0));
+            assert!(rule.is_quiescent());
+            assert!(!Rule1D::wolfram(1).is_quiescent());
+        }
+
+        #[test]
+        fn parse() {
+            let rule = Rule1D::parse(&["#.#.# => #", "..#.. => #", "##### => ."]).unwrap();
+            assert_eq!((rule.radius(), rule.states()), (2, 2));
+            assert_eq!(rule.next(&[1, 0, 1, 0, 1]), 1);
+            assert_eq!(rule.next(&[0, 0, 1, 0, 0]), 1);
+            assert_eq!(rule.next(&[1, 1, 1, 1, 1]), 0);
+            assert_eq!(rule.next(&[0, 1, 1, 1, 1]), 0);
+
+            let rule = Rule1D::parse(&[".#2 => 2"]).unwrap();
+            assert_eq!((rule.radius(), rule.states()), (1, 3));
+            assert_eq!(rule.next(&[0, 1, 2]), 2);
+
+            assert!(Rule1D::parse(&["#.#. => #"]).is_err());
+            assert!(Rule1D::parse(&["#.# => #", "#.#.# => #"]).is_err());
+            assert!(Rule1D::parse(&["#.# -> #"]).is_err());
+            assert!(Rule1D::parse(&["#x# => #"]).is_err());
+            assert!(Rule1D::parse(&[] as &[&str]).is_err());
+        }
+    }
+}
+pub use self::rule::Rule1D;
+
+mod period {
+    use std::fmt::Debug;
+
+    // An automaton whose rules don't depend on position, so a pattern that recurs, possibly
+    // translated, will keep recurring with the same period and translation
+    pub trait Periodic: Clone {
+        // How far a pattern moved, e.g. an offset along a line or a vector on a plane
+        type Shift: Copy + Debug + Eq;
+
+        fn generation(&self) -> u64;
+
+        fn tick(&mut self);
+
+        // The translation from `earlier`'s pattern to this one, if they're the same pattern
+        fn shift_from(&self, earlier: &Self) -> Option<Self::Shift>;
+
+        // Jumps ahead the given number of periods, without computing the intermediate generations
+        fn skip_periods(&mut self, period: &Period<Self::Shift>, count: u64);
+    }
+
+    // From generation `start` onwards, every `length` generations the pattern repeats, moved by `shift`
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub struct Period<S> {
+        pub start: u64,
+        pub length: u64,
+        pub shift: S,
+    }
+
+    impl<S: Copy + Debug + Eq> Period<S> {
+        pub fn new(start: u64, length: u64, shift: S) -> Period<S> {
+            assert!(length > 0);
+            Period { start, length, shift }
+        }
+
+        // Advances an automaton that's already in this cycle to the given generation, ticking at
+        // most length-1 times
+        pub fn advance_to<A: Periodic<Shift=S>>(&self, automaton: &mut A, generation: u64) {
+            assert!(automaton.generation() >= self.start, "Not yet in the cycle");
+            assert!(generation >= automaton.generation(), "Cannot go back in time");
+            let remaining = generation - automaton.generation();
+            automaton.skip_periods(self, remaining / self.length);
+            for _ in 0..remaining % self.length {
+                automaton.tick();
+            }
+        }
+
+        // A copy of the automaton at the given generation, which may be before the cycle starts
+        #[allow(dead_code)]
+        pub fn state_at<A: Periodic<Shift=S>>(&self, automaton: &A, generation: u64) -> A {
+            let mut automaton = automaton.clone();
+            while automaton.generation() < generation && automaton.generation() < self.start {
+                automaton.tick();
+            }
+            if automaton.generation() < generation {
+                self.advance_to(&mut automaton, generation);
+            }
+            automaton
+        }
+    }
+
+    // Finds the cycle that the automaton (eventually) enters, using Brent's algorithm so only a few
+    // copies of the automaton are kept, no matter how long it takes to find. This never returns if
+    // the automaton doesn't cycle, e.g. if its pattern grows forever; see find_period_within().
+    //   https://en.wikipedia.org/wiki/Cycle_detection#Brent's_algorithm
+    pub fn find_period<A: Periodic>(automaton: &A) -> Period<A::Shift> {
+        find_period_within(automaton, u64::MAX).expect("Cycles eventually")
+    }
+
+    // As find_period(), but gives up if the cycle hasn't repeated within max_generations
+    pub fn find_period_within<A: Periodic>(automaton: &A, max_generations: u64) -> Option<Period<A::Shift>> {
+        // First find the length of the cycle, by moving the tortoise to the hare at each power of
+        // two and seeing if the hare comes back around to it
+        let mut power = 1;
+        let mut length = 1;
+        let mut tortoise = automaton.clone();
+        let mut hare = automaton.clone();
+        hare.tick();
+        while hare.shift_from(&tortoise).is_none() {
+            if hare.generation() - automaton.generation() >= max_generations {
+                return None;
+            }
+            if power == length {
+                tortoise = hare.clone();
+                power *= 2;
+                length = 0;
+            }
+            hare.tick();
+            length += 1;
+        }
+
+        // Then find where the cycle starts, by keeping the hare a cycle ahead of the tortoise
+        let mut tortoise = automaton.clone();
+        let mut hare = automaton.clone();
+        for _ in 0..length {
+            hare.tick();
+        }
+        loop {
+            if let Some(shift) = hare.shift_from(&tortoise) {
+                return Some(Period::new(tortoise.generation(), length, shift));
+            }
+            tortoise.tick();
+            hare.tick();
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        // Counts up by one, wrapping from `end` back around to `start`, and recording how many
+        // times it has wrapped as the shift
+        #[derive(Clone, Debug)]
+        struct Counter {
+            value: u64,
+            start: u64,
+            end: u64,
+            laps: i64,
+            generation: u64,
+        }
+
+        impl Counter {
+            fn new(start: u64, end: u64) -> Counter {
+                Counter { value: 0, start, end, laps: 0, generation: 0 }
+            }
+        }
+
+        impl Periodic for Counter {
+            type Shift = i64;
+
+            fn generation(&self) -> u64 { self.generation }
+
+            fn tick(&mut self) {
+                self.generation += 1;
+                self.value += 1;
+                if self.value > self.end {
+                    self.value = self.start;
+                    self.laps += 1;
+                }
+            }
+
+            fn shift_from(&self, earlier: &Counter) -> Option<i64> {
+                if self.value == earlier.value { Some(self.laps - earlier.laps) } else { None }
+            }
+
+            fn skip_periods(&mut self, period: &Period<i64>, count: u64) {
+                self.generation += period.length * count;
+                self.laps += period.shift * count as i64;
+            }
+        }
+
+        #[test]
+        fn brent() {
+            let counter = Counter::new(3, 9);
+            let period = find_period(&counter);
+            assert_eq!(period, Period::new(3, 7, 1));
+
+            let later = period.state_at(&counter, 1_000_000);
+            assert_eq!((later.generation, later.value, later.laps), (1_000_000, 3 + (1_000_000 - 3) % 7, (1_000_000 - 3) / 7));
+            assert_eq!(period.state_at(&counter, 2).value, 2);
+
+            assert_eq!(find_period(&Counter::new(0, 0)), Period::new(0, 1, 1));
+            assert_eq!(find_period_within(&Counter::new(500, 1000), 100), None);
+            assert_eq!(find_period_within(&Counter::new(500, 1000), 10000), Some(Period::new(500, 501, 1)));
+        }
+    }
+}
+pub use self::period::{find_period, Period, Periodic};
+
+mod line {
+    use super::{symbol, Period, Periodic, Rule1D};
+    use std::cmp;
+    use std::fmt;
+    use crate::animation::Simulation;
+
+    // The cells are stored in a Vec that grows as needed, with offset tracking where position 0 is
+    #[derive(Clone)]
+    pub struct Automaton1D {
+        rule: Rule1D,
+        period: Option<Period<i64>>,
+        state: Vec<u8>,
+        offset: i64,
+        generation: u64,
+    }
+
+    impl Automaton1D {
+        pub fn new(initial: &[u8], rule: Rule1D) -> Automaton1D {
+            assert!(rule.is_quiescent(), "Cannot create life from nothing");
+            assert!(initial.iter().all(|&c| c < rule.states()), "Invalid initial state");
+            Automaton1D { rule, period: None, state: initial.to_vec(), offset: 0, generation: 0 }
+        }
+
+        // Ensures there are at least 2*radius empty cells on either side of the live cells, so that
+        // the cells that could come alive this generation have complete windows
+        fn shift_offsets(&mut self) {
+            let padding = 2 * self.rule.radius();
+            let first_live = self.state.iter().position(|&x| x != 0)
+                .expect("Expected a live cell");
+            let shift_by = padding - cmp::min(padding, first_live);
+            if shift_by > 0 {
+                let mut new_state = vec![0; shift_by];
+                new_state.extend_from_slice(&self.state);
+                self.state = new_state;
+                self.offset += shift_by as i64;
+            }
+
+            let last_live = self.state.iter().rposition(|&x| x != 0)
+                .expect("Expected a live cell");
+            let extend_to = cmp::max(self.state.len(), last_live + padding + 1);
+            self.state.resize(extend_to, 0);
+        }
+
+        fn live_bounds(state: &[u8]) -> Option<(usize, usize)> {
+            Some((state.iter().position(|&x| x != 0)?, state.iter().rposition(|&x| x != 0)?))
+        }
+
+        // Once a generation is just the previous generation shifted, every later generation will
+        // shift the same way. This is cheap to check every generation, unlike longer periods.
+        fn check_stability(&mut self, new_state: &[u8]) {
+            let shift = match (Automaton1D::live_bounds(&self.state), Automaton1D::live_bounds(new_state)) {
+                (Some(cur), Some(new)) if self.state[cur.0..=cur.1] == new_state[new.0..=new.1] =>
+                    new.0 as i64 - cur.0 as i64,
+                (None, None) => 0,
+                _ => return,
+            };
+            self.period = Some(Period::new(self.generation, 1, shift));
+        }
+
+        fn advance_impl(&mut self) {
+            if Automaton1D::live_bounds(&self.state).is_some() {
+                self.shift_offsets();
+
+                // The outermost radius cells can be skipped, as shift_offsets ensures their windows
+                // are empty (as are all further cells), and the rule is quiescent.
+                let radius = self.rule.radius();
+                let mut new_state = vec![0; self.state.len()];
+                for (i, window) in self.state.windows(2 * radius + 1).enumerate() {
+                    new_state[i + radius] = self.rule.next(window);
+                }
+
+                if self.period.is_none() {
+                    self.check_stability(&new_state);
+                }
+                self.state = new_state;
+            } else if self.period.is_none() {
+                self.check_stability(&[]);
+            }
+            self.generation += 1;
+        }
+
+        // Once the automaton's period is known the remaining generations are extrapolated, so
+        // this is fast even for enormous numbers of generations
+        pub fn advance(&mut self, generations: u64) {
+            let target = self.generation + generations;
+            while self.generation < target {
+                match self.period {
+                    Some(period) if self.generation >= period.start => {
+                        period.advance_to(self, target);
+                        return;
+                    },
+                    _ => self.advance_impl(),
+                }
+            }
+        }
+
+        // Finds the automaton's period even if it isn't just a translation, e.g. an oscillator or a
+        // pattern that moves every few generations, so that advance() can extrapolate it. Never
+        // returns if the automaton doesn't cycle.
+        #[allow(dead_code)]
+        pub fn find_period(&mut self) -> Period<i64> {
+            if self.period.is_none() {
+                self.period = Some(super::find_period(self));
+            }
+            self.period.expect("Was just set")
+        }
+
+        #[allow(dead_code)]
+        pub fn rule(&self) -> &Rule1D {
+            &self.rule
+        }
+
+        // The stored cells; cells[i] is at position i - offset
+        pub fn cells(&self) -> &[u8] {
+            &self.state
+        }
+
+        pub fn offset(&self) -> i64 {
+            self.offset
+        }
+
+        #[allow(dead_code)]
+        pub fn get(&self, position: i64) -> u8 {
+            let index = position + self.offset;
+            if index < 0 { return 0; }
+            self.state.get(index as usize).cloned().unwrap_or(0)
+        }
+
+        // The positions and states of all non-empty cells, from left to right
+        pub fn live_cells(&self) -> impl Iterator<Item=(i64, u8)> + '_ {
+            self.state.iter().enumerate()
+                .filter(|&(_, &c)| c != 0)
+                .map(move |(i, &c)| (i as i64 - self.offset, c))
+        }
+
+        pub fn generation(&self) -> u64 {
+            self.generation
+        }
+
+        pub fn is_stable(&self) -> bool {
+            self.period.is_some()
+        }
+
+        #[allow(dead_code)]
+        pub fn period(&self) -> Option<Period<i64>> {
+            self.period
+        }
+    }
+
+    impl Periodic for Automaton1D {
+        type Shift = i64;
+
+        fn generation(&self) -> u64 {
+            self.generation
+        }
+
+        fn tick(&mut self) {
+            self.advance_impl();
+        }
+
+        fn shift_from(&self, earlier: &Automaton1D) -> Option<i64> {
+            let bounds = Automaton1D::live_bounds(&self.state);
+            let earlier_bounds = Automaton1D::live_bounds(&earlier.state);
+            match (bounds, earlier_bounds) {
+                (Some(cur), Some(prev)) if self.state[cur.0..=cur.1] == earlier.state[prev.0..=prev.1] =>
+                    Some((cur.0 as i64 - self.offset) - (prev.0 as i64 - earlier.offset)),
+                (None, None) => Some(0),
+                _ => None,
+            }
+        }
+
+        fn skip_periods(&mut self, period: &Period<i64>, count: u64) {
+            self.generation += period.length * count;
+            self.offset -= period.shift * count as i64;
+        }
+    }
+
+    // Renders one generation per frame; there's no natural end, so callers should cap the frames
+    impl Simulation for Automaton1D {
+        fn step(&mut self) -> bool {
+            self.advance(1);
+            true
+        }
+
+        fn render(&self) -> String {
+            self.to_string()
+        }
+    }
+
+    // Renders the span of live cells
+    impl fmt::Display for Automaton1D {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match Automaton1D::live_bounds(&self.state) {
+                Some((first, last)) =>
+                    write!(f, "{}", self.state[first..=last].iter().map(|&c| symbol(c)).collect::<String>()),
+                None => Ok(()),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use super::super::parse_states;
+
+        #[test]
+        fn rule_90() {
+            // Sierpinski's triangle
+            let mut automaton = Automaton1D::new(&[1], Rule1D::wolfram(90));
+            let mut rows = vec!(automaton.to_string());
+            for _ in 0..4 {
+                automaton.advance(1);
+                rows.push(automaton.to_string());
+            }
+            assert_eq!(rows, vec!("#", "#.#", "#...#", "#.#.#.#", "#.......#"));
+            assert_eq!(automaton.live_cells().map(|(p, _)| p).collect::<Vec<_>>(), vec!(-4, 4));
+            assert!(!automaton.is_stable());
+        }
+
+        #[test]
+        fn gliders() {
+            // Rule 2 moves a lone cell left each generation, which is detected as stable
+            let mut automaton = Automaton1D::new(&[0, 0, 1], Rule1D::wolfram(2));
+            automaton.advance(2);
+            assert_eq!(automaton.period(), Some(Period::new(0, 1, -1)));
+            automaton.advance(1_000_000_000_000);
+            assert_eq!(automaton.live_cells().collect::<Vec<_>>(), vec!((2 - 1_000_000_000_002, 1)));
+            assert_eq!(automaton.get(-1_000_000_000_000), 1);
+            assert_eq!(automaton.get(2), 0);
+        }
+
+        #[test]
+        fn dies_out() {
+            let mut automaton = Automaton1D::new(&[1, 1], Rule1D::wolfram(0));
+            automaton.advance(3);
+            assert_eq!(automaton.period(), Some(Period::new(1, 1, 0)));
+            assert_eq!(automaton.live_cells().count(), 0);
+            assert_eq!(automaton.to_string(), "");
+        }
+
+        #[test]
+        fn period_found_before_cycle() {
+            // The cycle only starts once everything has died, so advance() has to tick there first
+            let mut automaton = Automaton1D::new(&[1, 1, 0, 1], Rule1D::wolfram(0));
+            assert_eq!(automaton.find_period(), Period::new(1, 1, 0));
+            assert_eq!(automaton.generation(), 0);
+            automaton.advance(1_000_000);
+            assert_eq!(automaton.generation(), 1_000_000);
+            assert_eq!(automaton.live_cells().count(), 0);
+        }
+
+        #[test]
+        fn three_states() {
+            // A wider radius, with each state promoting its left neighbor, and state 2 decaying
+            let rule = Rule1D::new(2, 3)
+                .rule(&[0, 0, 0, 1, 0], 1)
+                .rule(&[0, 0, 1, 0, 0], 2)
+                .rule(&[0, 0, 2, 0, 0], 0)
+                .rule(&[0, 0, 0, 2, 0], 0)
+                .rule(&[0, 0, 1, 1, 0], 2);
+            let mut automaton = Automaton1D::new(&parse_states("#").unwrap(), rule);
+            automaton.advance(1);
+            assert_eq!(automaton.to_string(), "#2");
+            assert_eq!(automaton.live_cells().collect::<Vec<_>>(), vec!((-1, 1), (0, 2)));
+        }
+
+        #[test]
+        fn slow_glider() {
+            // Takes two generations to move one cell right, which isn't caught by is_stable()
+            let rule = Rule1D::new(1, 3)
+                .rule(&[0, 1, 0], 2)
+                .rule(&[2, 0, 0], 1);
+            let mut automaton = Automaton1D::new(&[1], rule);
+            automaton.advance(10);
+            assert!(!automaton.is_stable());
+            // The search starts from the current generation
+            assert_eq!(automaton.find_period(), Period::new(10, 2, 1));
+            assert_eq!(automaton.generation(), 10);
+
+            automaton.advance(1_000_000_001 - 10);
+            assert_eq!(automaton.generation(), 1_000_000_001);
+            assert_eq!(automaton.live_cells().collect::<Vec<_>>(), vec!((500_000_000, 2)));
+
+            // Same as actually advancing
+            let mut slow = Automaton1D::new(&[1], automaton.rule().clone());
+            for _ in 0..11 {
+                slow.advance_impl();
+            }
+            let period = automaton.period().unwrap();
+            let fast = period.state_at(&Automaton1D::new(&[1], automaton.rule().clone()), 11);
+            assert_eq!(fast.live_cells().collect::<Vec<_>>(), slow.live_cells().collect::<Vec<_>>());
+        }
+
+        #[test]
+        #[should_panic(expected = "Cannot create life from nothing")]
+        fn not_quiescent() {
+            Automaton1D::new(&[1], Rule1D::wolfram(1));
+        }
+    }
+}
+pub use self::line::Automaton1D;
+
+mod rules2d {
+    use regex::Regex;
+    use std::str::FromStr;
+    use crate::error::ParseError;
+    use crate::euclid::{vector, Vector};
+
+    // Conway's Game of Life, in the rule file format
+    const LIFE: &str = "\
+        neighborhood moore 1\n\
+        state DEAD .\n\
+        state ALIVE #\n\
+        DEAD -> ALIVE if count(ALIVE) == 3\n\
+        ALIVE -> DEAD if count(ALIVE) < 2 or count(ALIVE) > 3\n";
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub enum Neighborhood {
+        // Every cell within the radius, including diagonally, i.e. a square
+        Moore(u32),
+        // Every cell within the radius by Manhattan distance, i.e. a diamond
+        VonNeumann(u32),
+    }
+
+    impl Neighborhood {
+        pub fn radius(&self) -> u32 {
+            match *self {
+                Neighborhood::Moore(radius) | Neighborhood::VonNeumann(radius) => radius,
+            }
+        }
+
+        // The offsets of every neighbor, in reading order, excluding the cell itself
+        pub fn offsets(&self) -> Vec<Vector> {
+            let radius = self.radius() as i32;
+            let mut offsets = Vec::new();
+            for y in -radius..=radius {
+                for x in -radius..=radius {
+                    let offset = vector(x, y);
+                    let included = match self {
+                        Neighborhood::Moore(_) => true,
+                        Neighborhood::VonNeumann(_) => offset.grid_len() as i32 <= radius,
+                    };
+                    if included && offset != vector(0, 0) {
+                        offsets.push(offset);
+                    }
+                }
+            }
+            offsets
+        }
+    }
+
+    impl FromStr for Neighborhood {
+        type Err = ParseError;
+
+        // e.g. "moore 1" or "von-neumann 2"
+        fn from_str(s: &str) -> Result<Self, ParseError> {
+            let parts: Vec<_> = s.split_whitespace().collect();
+            match parts[..] {
+                ["moore", radius] => Ok(Neighborhood::Moore(radius.parse()?)),
+                ["von-neumann", radius] => Ok(Neighborhood::VonNeumann(radius.parse()?)),
+                _ => Err(ParseError::Malformed(format!("neighborhood: {}", s))),
+            }
+        }
+    }
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub enum Comparison { Lt, Le, Eq, Ne, Ge, Gt }
+
+    impl Comparison {
+        fn compare(&self, a: usize, b: usize) -> bool {
+            match self {
+                Comparison::Lt => a < b,
+                Comparison::Le => a <= b,
+                Comparison::Eq => a == b,
+                Comparison::Ne => a != b,
+                Comparison::Ge => a >= b,
+                Comparison::Gt => a > b,
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub enum Condition {
+        Always,
+        // The number of neighbors in any of the given states, compared to a value
+        Count(Vec<u8>, Comparison, usize),
+        Not(Box<Condition>),
+        All(Vec<Condition>),
+        Any(Vec<Condition>),
+    }
+
+    impl Condition {
+        // counts[s] is the number of neighbors in state s
+        pub fn matches(&self, counts: &[usize]) -> bool {
+            match self {
+                Condition::Always => true,
+                Condition::Count(states, comparison, value) =>
+                    comparison.compare(states.iter().map(|&s| counts[s as usize]).sum(), *value),
+                Condition::Not(condition) => !condition.matches(counts),
+                Condition::All(conditions) => conditions.iter().all(|c| c.matches(counts)),
+                Condition::Any(conditions) => conditions.iter().any(|c| c.matches(counts)),
+            }
+        }
+    }
+
+    // Cells in the `from` state (or any state, if None) switch to `to` when the condition matches
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub struct Transition {
+        from: Option<u8>,
+        to: u8,
+        condition: Condition,
+    }
+
+    // A set of named states and the transitions between them, which can be parsed from a rule file:
+    //
+    //   # Comments and blank lines are ignored
+    //   neighborhood moore 1
+    //   state OPEN .
+    //   state TREES |
+    //   OPEN -> TREES if count(TREES) >= 3
+    //   TREES -> OPEN unless count(TREES) >= 1 and count(OPEN, TREES) < 8
+    //   * -> OPEN if count(TREES) == 0 or count(OPEN) > 6
+    //
+    // Each state has a name and the character representing it in a grid; the first state is the
+    // background. Each cell takes the first transition that applies to it, or else stays as it is.
+    // `and` binds tighter than `or`. The neighborhood (moore or von-neumann, with a radius)
+    // defaults to moore 1.
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub struct Rules2D {
+        neighborhood: Neighborhood,
+        offsets: Vec<Vector>,
+        states: Vec<(String, char)>,
+        transitions: Vec<Transition>,
+    }
+
+    impl Rules2D {
+        #[allow(dead_code)]
+        pub fn game_of_life() -> Rules2D {
+            LIFE.parse().expect("Valid rules")
+        }
+
+        pub fn neighborhood(&self) -> Neighborhood {
+            self.neighborhood
+        }
+
+        // Cached, since this is needed for every cell
+        pub fn offsets(&self) -> &[Vector] {
+            &self.offsets
+        }
+
+        pub fn states(&self) -> usize {
+            self.states.len()
+        }
+
+        pub fn state(&self, name: &str) -> Option<u8> {
+            self.states.iter().position(|(n, _)| n == name).map(|i| i as u8)
+        }
+
+        #[allow(dead_code)]
+        pub fn name(&self, state: u8) -> &str {
+            &self.states[state as usize].0
+        }
+
+        pub fn symbol(&self, state: u8) -> char {
+            self.states[state as usize].1
+        }
+
+        pub fn state_for(&self, symbol: char) -> Option<u8> {
+            self.states.iter().position(|&(_, c)| c == symbol).map(|i| i as u8)
+        }
+
+        pub fn next(&self, state: u8, counts: &[usize]) -> u8 {
+            self.transitions.iter()
+                .find(|t| t.from.is_none_or(|from| from == state) && t.condition.matches(counts))
+                .map_or(state, |t| t.to)
+        }
+
+        fn parse_state(&self, name: &str) -> Result<u8, ParseError> {
+            self.state(name).ok_or_else(|| ParseError::Malformed(format!("unknown state: {}", name)))
+        }
+
+        fn parse_condition(&self, s: &str) -> Result<Condition, ParseError> {
+            lazy_static! {
+                static ref COUNT_RE: Regex =
+                    Regex::new(r"^count\(([\w\s,]+)\)\s*(<|<=|==|!=|>=|>)\s*(\d+)$").unwrap();
+            }
+
+            let mut any = Vec::new();
+            for conjunction in s.split(" or ") {
+                let mut all = Vec::new();
+                for clause in conjunction.split(" and ") {
+                    let caps = regex_captures!(COUNT_RE, clause.trim())?;
+                    let states = capture_group!(caps, 1).split(',')
+                        .map(|name| self.parse_state(name.trim()))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let comparison = match capture_group!(caps, 2) {
+                        "<" => Comparison::Lt,
+                        "<=" => Comparison::Le,
+                        "==" => Comparison::Eq,
+                        "!=" => Comparison::Ne,
+                        ">=" => Comparison::Ge,
+                        ">" => Comparison::Gt,
+                        _ => unreachable!(),
+                    };
+                    all.push(Condition::Count(states, comparison, capture_group!(caps, 3).parse()?));
+                }
+                any.push(if all.len() == 1 { all.remove(0) } else { Condition::All(all) });
+            }
+            Ok(if any.len() == 1 { any.remove(0) } else { Condition::Any(any) })
+        }
+
+        fn parse_transition(&self, line: &str) -> Result<Transition, ParseError> {
+            lazy_static! {
+                static ref TRANSITION_RE: Regex =
+                    Regex::new(r"^(\*|\w+)\s*->\s*(\w+)(?:\s+(if|unless)\s+(.+))?$").unwrap();
+            }
+
+            let caps = regex_captures!(TRANSITION_RE, line)?;
+            let from = match capture_group!(caps, 1) {
+                "*" => None,
+                name => Some(self.parse_state(name)?),
+            };
+            let to = self.parse_state(capture_group!(caps, 2))?;
+            let condition = match caps.get(3).map(|m| m.as_str()) {
+                Some("if") => self.parse_condition(capture_group!(caps, 4))?,
+                Some(_) => Condition::Not(Box::new(self.parse_condition(capture_group!(caps, 4))?)),
+                None => Condition::Always,
+            };
+            Ok(Transition { from, to, condition })
+        }
+    }
+
+    impl FromStr for Rules2D {
+        type Err = ParseError;
+
+        // States and the neighborhood are read first, so they can be declared in any order
+        fn from_str(s: &str) -> Result<Self, ParseError> {
+            let lines: Vec<_> = s.lines().map(|l| l.trim())
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .collect();
+
+            let mut rules = Rules2D {
+                neighborhood: Neighborhood::Moore(1), offsets: Vec::new(), states: Vec::new(), transitions: Vec::new() };
+            for line in &lines {
+                if let Some(neighborhood) = line.strip_prefix("neighborhood ") {
+                    rules.neighborhood = neighborhood.parse()?;
+                } else if let Some(state) = line.strip_prefix("state ") {
+                    let parts: Vec<_> = state.split_whitespace().collect();
+                    match parts[..] {
+                        [name, symbol] if symbol.chars().count() == 1 => {
+                            let symbol = symbol.chars().next().expect("One char");
+                            if rules.state(name).is_some() || rules.state_for(symbol).is_some() {
+                                return Err(ParseError::Malformed(format!("duplicate state: {}", line)));
+                            }
+                            rules.states.push((name.to_string(), symbol));
+                        },
+                        _ => return Err(ParseError::Malformed(format!("state: {}", line))),
+                    }
+                }
+            }
+            if rules.states.len() < 2 || rules.states.len() > u8::MAX as usize {
+                return Err(ParseError::Malformed(format!("number of states: {}", rules.states.len())));
+            }
+            rules.offsets = rules.neighborhood.offsets();
+
+            for line in lines {
+                if !line.starts_with("neighborhood ") && !line.starts_with("state ") {
+                    let transition = rules.parse_transition(line)?;
+                    rules.transitions.push(transition);
+                }
+            }
+            Ok(rules)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn neighborhoods() {
+            assert_eq!(Neighborhood::Moore(1).offsets().len(), 8);
+            assert_eq!(Neighborhood::Moore(2).offsets().len(), 24);
+            assert_eq!(Neighborhood::VonNeumann(1).offsets(),
+                       vec!(vector(0, -1), vector(-1, 0), vector(1, 0), vector(0, 1)));
+            assert_eq!(Neighborhood::VonNeumann(2).offsets().len(), 12);
+            assert_eq!("von-neumann 3".parse(), Ok(Neighborhood::VonNeumann(3)));
+            assert!("hexagonal 1".parse::<Neighborhood>().is_err());
+        }
+
+        #[test]
+        fn life() {
+            let rules = Rules2D::game_of_life();
+            assert_eq!((rules.states(), rules.state("ALIVE"), rules.symbol(1)), (2, Some(1), '#'));
+            assert_eq!(rules.next(0, &[5, 3]), 1);
+            assert_eq!(rules.next(0, &[6, 2]), 0);
+            assert_eq!(rules.next(1, &[6, 2]), 1);
+            assert_eq!(rules.next(1, &[7, 1]), 0);
+            assert_eq!(rules.next(1, &[4, 4]), 0);
+        }
+
+        #[test]
+        fn parse() {
+            let rules: Rules2D = "
+                # Cells fade through the states
+                A -> B unless count(A, B) > 1 and count(C) == 0
+                * -> C if count(B) >= 4
+                neighborhood von-neumann 1
+                state A .
+                state B o
+                state C O
+                B -> A".parse().unwrap();
+            assert_eq!(rules.neighborhood(), Neighborhood::VonNeumann(1));
+            assert_eq!(rules.offsets().len(), 4);
+            assert_eq!(rules.state_for('O'), Some(2));
+            assert_eq!(rules.name(1), "B");
+            assert_eq!(rules.next(0, &[1, 1, 0]), 0);
+            assert_eq!(rules.next(0, &[1, 0, 1]), 1);
+            assert_eq!(rules.next(0, &[3, 1, 0]), 0);
+            assert_eq!(rules.next(1, &[0, 4, 0]), 2);
+            assert_eq!(rules.next(1, &[4, 0, 0]), 0);
+            assert_eq!(rules.next(2, &[4, 0, 0]), 2);
+
+            assert!("state A .".parse::<Rules2D>().is_err());
+            assert!("state A .\nstate B .".parse::<Rules2D>().is_err());
+            assert!("state A .\nstate B #\nA -> C".parse::<Rules2D>().is_err());
+            assert!("state A .\nstate B #\nA -> B if count(B) ~ 3".parse::<Rules2D>().is_err());
+            assert!("state A .\nstate B #\nA => B".parse::<Rules2D>().is_err());
+        }
+    }
+}
+pub use self::rules2d::Rules2D;
+
+mod plane {
+    use super::{Period, Periodic, Rules2D};
+    use std::convert::TryFrom;
+    use std::fmt;
+    use crate::animation::Simulation;
+    use crate::error::ParseError;
+    use crate::euclid::{vector, Grid, Point, Rect, Vector};
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub enum Boundary {
+        // Only the cells of the initial grid exist, and cells past the edge aren't counted
+        Fixed,
+        // The plane extends forever, filled with the background state, and the grid grows and
+        // shrinks to fit the pattern
+        Unbounded,
+    }
+
+    #[derive(Clone)]
+    pub struct Automaton2D {
+        rules: Rules2D,
+        boundary: Boundary,
+        cells: Grid<u8>,
+        generation: u64,
+        period: Option<Period<Vector>>,
+    }
+
+    impl Automaton2D {
+        pub fn new(rules: Rules2D, cells: Grid<u8>, boundary: Boundary) -> Automaton2D {
+            assert!(cells.iter().all(|(_, &c)| (c as usize) < rules.states()), "Invalid state");
+            if boundary == Boundary::Unbounded {
+                let mut counts = vec![0; rules.states()];
+                counts[0] = rules.offsets().len();
+                assert_eq!(rules.next(0, &counts), 0, "Cannot create life from nothing");
+            }
+            Automaton2D { rules, boundary, cells, generation: 0, period: None }
+        }
+
+        // Parses a grid of the rules' state symbols, with the top-left cell at the origin
+        pub fn parse(rules: Rules2D, s: &str, boundary: Boundary) -> Result<Automaton2D, ParseError> {
+            let cells = Grid::from_ascii(s, |c| rules.state_for(c))?;
+            Ok(Automaton2D::new(rules, cells, boundary))
+        }
+
+        pub fn rules(&self) -> &Rules2D {
+            &self.rules
+        }
+
+        #[allow(dead_code)]
+        pub fn cells(&self) -> &Grid<u8> {
+            &self.cells
+        }
+
+        // Cells outside the grid are in the background state
+        pub fn get(&self, coord: &Point) -> u8 {
+            self.cells.get(coord).cloned().unwrap_or(0)
+        }
+
+        pub fn generation(&self) -> u64 {
+            self.generation
+        }
+
+        // The number of cells in each state, indexed by state
+        pub fn counts(&self) -> Vec<usize> {
+            let mut counts = vec![0; self.rules.states()];
+            for (_, &cell) in self.cells.iter() {
+                counts[cell as usize] += 1;
+            }
+            counts
+        }
+
+        // The smallest rect containing every non-background cell
+        pub fn live_bounds(&self) -> Option<Rect> {
+            Rect::bounding(self.cells.iter().filter(|&(_, &c)| c != 0).map(|(p, _)| p))
+        }
+
+        fn next_cell(&self, coord: Point, counts: &mut [usize]) -> u8 {
+            counts.iter_mut().for_each(|c| *c = 0);
+            for offset in self.rules.offsets() {
+                match self.cells.get(&(coord + offset)) {
+                    Some(&state) => counts[state as usize] += 1,
+                    None if self.boundary == Boundary::Unbounded => counts[0] += 1,
+                    None => {},
+                }
+            }
+            self.rules.next(self.get(&coord), counts)
+        }
+
+        fn tick_impl(&mut self) {
+            let bounds = match self.boundary {
+                Boundary::Fixed => Some(self.cells.bounds()),
+                // Any cell that changes must be within a radius of a live cell
+                Boundary::Unbounded => self.live_bounds().map(|b| {
+                    let radius = self.rules.neighborhood().radius() as i32;
+                    Rect::new(b.min() - vector(radius, radius), b.max() + vector(radius, radius))
+                }),
+            };
+            if let Some(bounds) = bounds {
+                let mut counts = vec![0; self.rules.states()];
+                let cells = bounds.points().map(|p| self.next_cell(p, &mut counts)).collect();
+                self.cells = Grid::from_vec(bounds, cells);
+            }
+            self.generation += 1;
+        }
+
+        // Once the automaton's period is known (see find_period()) the remaining generations are
+        // extrapolated. Panics if a moving pattern would leave the i32 coordinate space, since
+        // there's no way to represent it.
+        pub fn advance(&mut self, generations: u64) {
+            let target = self.generation + generations;
+            while self.generation < target {
+                match self.period {
+                    Some(period) if self.generation >= period.start => {
+                        period.advance_to(self, target);
+                        return;
+                    },
+                    _ => self.tick_impl(),
+                }
+            }
+        }
+
+        // Never returns if the automaton doesn't cycle
+        pub fn find_period(&mut self) -> Period<Vector> {
+            if self.period.is_none() {
+                self.period = Some(super::find_period(self));
+            }
+            self.period.expect("Was just set")
+        }
+    }
+
+    impl Periodic for Automaton2D {
+        type Shift = Vector;
+
+        fn generation(&self) -> u64 {
+            self.generation
+        }
+
+        fn tick(&mut self) {
+            self.tick_impl();
+        }
+
+        fn shift_from(&self, earlier: &Automaton2D) -> Option<Vector> {
+            if self.boundary == Boundary::Fixed {
+                return if self.cells == earlier.cells { Some(vector(0, 0)) } else { None };
+            }
+            match (self.live_bounds(), earlier.live_bounds()) {
+                (Some(bounds), Some(earlier_bounds)) => {
+                    let shift = bounds.min() - earlier_bounds.min();
+                    let same = bounds.size() == earlier_bounds.size() &&
+                        bounds.points().all(|p| self.cells[p] == earlier.cells[p - shift]);
+                    if same { Some(shift) } else { None }
+                },
+                (None, None) => Some(vector(0, 0)),
+                _ => None,
+            }
+        }
+
+        fn skip_periods(&mut self, period: &Period<Vector>, count: u64) {
+            if period.shift != vector(0, 0) {
+                let bounds = self.cells.bounds();
+                let moved = i32::try_from(count).ok()
+                    .and_then(|count| period.shift.checked_mul(count))
+                    .and_then(|shift| Some((shift, Rect::new(bounds.min().checked_add(&shift)?, bounds.max().checked_add(&shift)?))));
+                let (shift, bounds) = moved.expect("Pattern moved beyond i32 coordinates");
+                let old = &self.cells;
+                self.cells = Grid::from_fn(bounds, |p| old[p - shift]);
+            }
+            self.generation += period.length * count;
+        }
+    }
+
+    // Renders one generation per frame; there's no natural end, so callers should cap the frames
+    impl Simulation for Automaton2D {
+        fn step(&mut self) -> bool {
+            self.advance(1);
+            true
+        }
+
+        fn render(&self) -> String {
+            self.to_string()
+        }
+    }
+
+    // Renders the whole grid, or for an unbounded plane just the region containing live cells
+    impl fmt::Display for Automaton2D {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let bounds = match self.boundary {
+                Boundary::Fixed => self.cells.bounds(),
+                Boundary::Unbounded => match self.live_bounds() {
+                    Some(bounds) => bounds,
+                    None => return Ok(()),
+                },
+            };
+            for p in bounds.points() {
+                write!(f, "{}", self.rules.symbol(self.cells[p]))?;
+                if p.x == bounds.max().x {
+                    writeln!(f)?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::euclid::point;
+
+        fn life(s: &str) -> Automaton2D {
+            Automaton2D::parse(Rules2D::game_of_life(), s, Boundary::Unbounded).unwrap()
+        }
+
+        #[test]
+        fn blinker() {
+            let mut blinker = life(".....\n..#..\n..#..\n..#..\n.....");
+            blinker.advance(1);
+            assert_eq!(blinker.to_string(), "###\n");
+            assert_eq!(blinker.live_bounds(), Some(Rect::new(point(1, 2), point(3, 2))));
+            assert_eq!(blinker.find_period(), Period::new(1, 2, vector(0, 0)));
+            blinker.advance(1_000_000_000 - 1);
+            assert_eq!(blinker.to_string(), "#\n#\n#\n");
+        }
+
+        #[test]
+        fn glider() {
+            let mut glider = life(".#.\n..#\n###");
+            let period = glider.find_period();
+            assert_eq!(period, Period::new(0, 4, vector(1, 1)));
+            glider.advance(4000);
+            assert_eq!(glider.live_bounds(), Some(Rect::new(point(1000, 1000), point(1002, 1002))));
+            assert_eq!(glider.to_string(), ".#.\n..#\n###\n");
+
+            // Agrees with actually ticking
+            let mut slow = life(".#.\n..#\n###");
+            for _ in 0..4002 {
+                slow.tick_impl();
+            }
+            glider.advance(2);
+            assert_eq!(glider.to_string(), slow.to_string());
+            assert_eq!(glider.live_bounds(), slow.live_bounds());
+        }
+
+        #[test]
+        fn glider_to_the_edge() {
+            let mut glider = life(".#.\n..#\n###");
+            glider.find_period();
+            let periods = i32::MAX as u64 - 2;
+            glider.advance(4 * periods);
+            assert_eq!(glider.generation(), 4 * periods);
+            let min = point(i32::MAX - 2, i32::MAX - 2);
+            assert_eq!(glider.live_bounds(), Some(Rect::new(min, point(i32::MAX, i32::MAX))));
+        }
+
+        #[test]
+        #[should_panic(expected = "Pattern moved beyond i32 coordinates")]
+        fn glider_past_the_edge() {
+            let mut glider = life(".#.\n..#\n###");
+            glider.find_period();
+            glider.advance(4 * (1 << 31));
+        }
+
+        #[test]
+        fn fixed_boundary() {
+            // The edges of the grid aren't wrapped or padded, so the block in the corner survives
+            // while the blinker on the edge dies
+            let mut automaton = Automaton2D::parse(Rules2D::game_of_life(), "##...\n##...\n....#\n....#\n....#", Boundary::Fixed).unwrap();
+            automaton.advance(1);
+            assert_eq!(automaton.to_string(), "##...\n##...\n.....\n...##\n.....\n");
+            automaton.advance(1);
+            assert_eq!(automaton.to_string(), "##...\n##...\n.....\n.....\n.....\n");
+            assert_eq!(automaton.counts(), vec!(21, 4));
+            assert_eq!(automaton.get(&point(-1, -1)), 0);
+        }
+
+        #[test]
+        fn dies_out() {
+            let mut automaton = life("#");
+            automaton.advance(1);
+            assert_eq!(automaton.live_bounds(), None);
+            assert_eq!(automaton.to_string(), "");
+            assert_eq!(automaton.find_period(), Period::new(1, 1, vector(0, 0)));
+        }
+    }
+}
+pub use self::plane::{Automaton2D, Boundary};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn states() {
+        assert_eq!(parse_states("#.#.#"), Ok(vec!(1, 0, 1, 0, 1)));
+        assert_eq!(parse_states(".#2"), Ok(vec!(0, 1, 2)));
+        assert!(parse_states("#x").is_err());
+        assert_eq!(symbol(2), '2');
+    }
+}
@@ -1,125 +1,775 @@
 // Referenced https://docs.rs/rusttype/0.5.2/src/rusttype/geometry.rs.html
 // Other resources:
 //   https://crates.io/crates/euclid - https://doc.servo.org/src/euclid/point.rs.html
-mod point {
-    use super::*;
-    use std::fmt;
-    use std::ops::{Add,AddAssign,Sub};
-    use regex::Regex;
-    use std::str::FromStr;
-    use crate::error::ParseError;
 
-    #[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
-    pub struct Point {
-        pub x: i32,
-        pub y: i32,
-    }
+// Defines a Point and Vector pair (along with point() and vector() constructors) with one field per
+// listed axis, e.g. euclid_space!(x, y) for the 2D types below. euclid3d and day 25 use this to
+// define 3D and 4D spaces. Coordinates are i32s unless a signed scalar and its unsigned counterpart
+// (used for lengths) are specified, e.g. euclid_space!(i64, u64; x, y).
+//
+// Arithmetic panics on overflow, even in release builds, rather than silently wrapping; the
+// checked_*() methods are available for callers that want to handle overflow themselves.
+macro_rules! euclid_space {
+    ($($axis:ident),+) => { euclid_space!(i32, u32; $($axis),+); };
+    ($scalar:ty, $unsigned:ty; $($axis:ident),+) => {
+        // Not every space uses every operation
+        #[allow(dead_code)]
+        mod point {
+            use super::*;
+            use std::cmp;
+            use std::fmt;
+            use std::ops::{Add,AddAssign,Sub,SubAssign};
+            use regex::Regex;
+            use std::str::FromStr;
+            use crate::error::ParseError;
 
-    #[inline]
-    pub const fn point(x: i32, y: i32) -> Point {
-        Point { x, y }
-    }
+            #[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
+            pub struct Point {
+                $(pub $axis: $scalar),+
+            }
 
+            #[inline]
+            pub const fn point($($axis: $scalar),+) -> Point {
+                Point { $($axis),+ }
+            }
 
-    impl Point {
-        pub const ORIGIN: Point = point(0, 0);
+            impl Point {
+                pub const DIMENSIONS: usize = [$(stringify!($axis)),+].len();
+                pub const ORIGIN: Point = Point { $($axis: 0),+ };
+
+                pub fn bounding_box(points: impl IntoIterator<Item = Point>) -> Option<(Point, Point)> {
+                    points.into_iter().fold(None, |r , c|
+                        match r {
+                            Some((min, max)) => {
+                                Some((
+                                    Point { $($axis: cmp::min(min.$axis, c.$axis)),+ },
+                                    Point { $($axis: cmp::max(max.$axis, c.$axis)),+ },
+                                ))
+                            },
+                            None => Some((c, c)),
+                        }
+                    )
+                }
+
+                pub fn checked_add(&self, vec: &Vector) -> Option<Point> {
+                    Some(Point { $($axis: self.$axis.checked_add(vec.$axis)?),+ })
+                }
+
+                pub fn checked_sub(&self, point: &Point) -> Option<Vector> {
+                    Some(Vector { $($axis: self.$axis.checked_sub(point.$axis)?),+ })
+                }
+
+                pub fn in_bounds(&self, min: Point, max: Point) -> bool {
+                    $(assert!(min.$axis <= max.$axis);)+
+                    true $(&& min.$axis <= self.$axis && max.$axis >= self.$axis)+
+                }
+            }
+
+            impl Add<&Vector> for Point {
+                type Output = Point;
+
+                fn add(self, vec: &Vector) -> Point { self.checked_add(vec).expect("Point overflowed") }
+            }
+
+            impl Add<&Vector> for &Point {
+                type Output = Point;
+
+                fn add(self, vec: &Vector) -> Point { self.checked_add(vec).expect("Point overflowed") }
+            }
+
+            impl Add<Vector> for &Point {
+                type Output = Point;
+
+                fn add(self, vec: Vector) -> Point { self.checked_add(&vec).expect("Point overflowed") }
+            }
+
+            impl Add<Vector> for Point {
+                type Output = Point;
+
+                fn add(self, vec: Vector) -> Point { self.checked_add(&vec).expect("Point overflowed") }
+            }
+
+            impl AddAssign<Vector> for Point {
+                fn add_assign(&mut self, vec: Vector) { *self = *self + vec; }
+            }
+
+            impl Sub<Vector> for Point {
+                type Output = Point;
 
-        pub fn bounding_box(points: impl IntoIterator<Item = Point>) -> Option<(Point, Point)> {
-            points.into_iter().fold(None, |r , c|
-                match r {
-                    Some((min, max)) => {
-                        Some((
-                            point(std::cmp::min(min.x, c.x), std::cmp::min(min.y, c.y)),
-                            point(std::cmp::max(max.x, c.x), std::cmp::max(max.y, c.y))
-                        ))
-                    },
-                    None => Some((c, c)),
+                fn sub(self, vec: Vector) -> Point {
+                    Point { $($axis: self.$axis.checked_sub(vec.$axis).expect("Point overflowed")),+ }
                 }
-            )
+            }
+
+            impl SubAssign<Vector> for Point {
+                fn sub_assign(&mut self, vec: Vector) { *self = *self - vec; }
+            }
+
+            impl Sub for Point {
+                type Output = Vector;
+
+                fn sub(self, point: Point) -> Vector { self.checked_sub(&point).expect("Vector overflowed") }
+            }
+
+            impl Sub<&Point> for Point {
+                type Output = Vector;
+
+                fn sub(self, point: &Point) -> Vector { self.checked_sub(point).expect("Vector overflowed") }
+            }
+
+            impl Sub for &Point {
+                type Output = Vector;
+
+                fn sub(self, point: &Point) -> Vector { self.checked_sub(point).expect("Vector overflowed") }
+            }
+
+            impl FromStr for Point {
+                type Err = ParseError;
+
+                fn from_str(s: &str) -> Result<Self, ParseError> {
+                    lazy_static! {
+                        // e.g. r"^\(?([^(),]+),([^(),]+)\)?$" for 2D points
+                        static ref RE: Regex = Regex::new(&format!(r"^\(?{}\)?$",
+                            vec!(r"([^(),]+)"; Point::DIMENSIONS).join(","))).unwrap();
+                    }
+
+                    let caps = regex_captures!(RE, s)?;
+                    let mut coords = caps.iter().skip(1)
+                        .map(|c| c.expect("valid capture group").as_str().trim().parse::<$scalar>());
+                    Ok(Point { $($axis: coords.next().expect("One group per axis")?),+ })
+                }
+            }
+
+            impl fmt::Debug for Point {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    write!(f, "({})", [$(self.$axis.to_string()),+].join(", "))
+                }
+            }
+
+            impl fmt::Display for Point {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    write!(f, "{:?}", self)
+                }
+            }
         }
+        #[allow(unused_imports)]
+        pub use self::point::{Point,point};
+
+        #[allow(dead_code)]
+        mod vector {
+            use std::fmt;
+            use std::ops::{Add,Mul,Neg,Sub};
+            use std::str::FromStr;
+            use crate::error::ParseError;
+
+            #[derive(Copy, Clone, PartialEq, Eq, Hash)]
+            pub struct Vector {
+                $(pub $axis: $scalar),+
+            }
+
+            #[inline]
+            pub const fn vector($($axis: $scalar),+) -> Vector {
+                Vector { $($axis),+ }
+            }
+
+            impl Vector {
+                pub const ZERO: Vector = Vector { $($axis: 0),+ };
+
+                pub fn abs(&self) -> Vector {
+                    Vector { $($axis: self.$axis.checked_abs().expect("Vector overflowed")),+ }
+                }
 
-        pub fn in_bounds(&self, min: Point, max: Point) -> bool {
-            assert!(min.x <= max.x);
-            assert!(min.y <= max.y);
-            min.x <= self.x && min.y <= self.y && max.x >= self.x && max.y >= self.y
+                pub fn checked_add(&self, vec: &Vector) -> Option<Vector> {
+                    Some(Vector { $($axis: self.$axis.checked_add(vec.$axis)?),+ })
+                }
+
+                pub fn checked_neg(&self) -> Option<Vector> {
+                    Some(Vector { $($axis: self.$axis.checked_neg()?),+ })
+                }
+
+                pub fn checked_mul(&self, scalar: $scalar) -> Option<Vector> {
+                    Some(Vector { $($axis: self.$axis.checked_mul(scalar)?),+ })
+                }
+
+                // Euclidean length
+                pub fn len(&self) -> f64 {
+                    [$(self.$axis as f64),+].iter().map(|c| c * c).sum::<f64>().sqrt()
+                }
+
+                // Manhattan length
+                pub fn grid_len(&self) -> $unsigned {
+                    let len: Option<$unsigned> = Some(0);
+                    $(let len = len.and_then(|l| l.checked_add(self.$axis.unsigned_abs()));)+
+                    len.expect("Length overflowed")
+                }
+
+                // Chebyshev length, i.e. the number of king's moves
+                pub fn chebyshev_len(&self) -> $unsigned {
+                    [$(self.$axis.unsigned_abs()),+].iter().copied().max().expect("Non-empty")
+                }
+            }
+
+            impl Add for Vector {
+                type Output = Vector;
+
+                fn add(self, vec: Vector) -> Vector { self.checked_add(&vec).expect("Vector overflowed") }
+            }
+
+            impl Sub for Vector {
+                type Output = Vector;
+
+                fn sub(self, vec: Vector) -> Vector {
+                    Vector { $($axis: self.$axis.checked_sub(vec.$axis).expect("Vector overflowed")),+ }
+                }
+            }
+
+            impl Neg for Vector {
+                type Output = Vector;
+
+                fn neg(self) -> Vector { self.checked_neg().expect("Vector overflowed") }
+            }
+
+            impl Mul<$scalar> for Vector {
+                type Output = Vector;
+
+                fn mul(self, scalar: $scalar) -> Vector { self.checked_mul(scalar).expect("Vector overflowed") }
+            }
+
+            impl FromStr for Vector {
+                type Err = ParseError;
+
+                fn from_str(s: &str) -> Result<Self, ParseError> {
+                    // Just reuse point's parser
+                    let p: super::Point = s.parse()?;
+                    Ok(Vector { $($axis: p.$axis),+ })
+                }
+            }
+
+            impl fmt::Debug for Vector {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    write!(f, "({})", [$(self.$axis.to_string()),+].join(", "))
+                }
+            }
+
+            impl fmt::Display for Vector {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    write!(f, "{:?}", self)
+                }
+            }
         }
+        #[allow(unused_imports)]
+        pub use self::vector::{Vector,vector};
     }
+}
 
-    impl Add<&Vector> for Point {
-        type Output = Point;
+// Defines an axis-aligned box type (e.g. a rectangle) named $name over the Point and Vector types in
+// scope, which must have been defined by euclid_space!() with the same scalars and axes. Boxes are
+// non-empty and their bounds are inclusive, like those returned by Point::bounding_box(). $measure
+// names the method returning the number of points in the box, e.g. area or volume.
+macro_rules! euclid_box {
+    ($name:ident, $measure:ident; $($axis:ident),+) => { euclid_box!($name, $measure, i32, u32; $($axis),+); };
+    ($name:ident, $measure:ident, $scalar:ty, $unsigned:ty; $($axis:ident),+) => {
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+        pub struct $name {
+            min: Point,
+            max: Point,
+        }
+
+        // Not every space uses every operation
+        #[allow(dead_code)]
+        impl $name {
+            pub fn new(min: Point, max: Point) -> $name {
+                $(assert!(min.$axis <= max.$axis, "{:?} is not less than {:?}", min, max);)+
+                $name { min, max }
+            }
+
+            // A box starting at origin spanning size points along each axis
+            pub fn with_size(origin: Point, size: Vector) -> $name {
+                $(assert!(size.$axis > 0, "Invalid size {:?}", size);)+
+                $name::new(origin, Point { $($axis: origin.$axis.checked_add(size.$axis - 1).expect("Box overflowed")),+ })
+            }
+
+            pub fn bounding(points: impl IntoIterator<Item = Point>) -> Option<$name> {
+                Point::bounding_box(points).map(|(min, max)| $name::new(min, max))
+            }
+
+            pub fn min(&self) -> Point { self.min }
+            pub fn max(&self) -> Point { self.max }
+
+            pub fn size(&self) -> Vector {
+                let diff = self.max - self.min;
+                Vector { $($axis: diff.$axis.checked_add(1).expect("Size overflowed")),+ }
+            }
+
+            pub fn $measure(&self) -> $unsigned {
+                let size = self.size();
+                let measure: Option<$unsigned> = Some(1);
+                $(let measure = measure.and_then(|m| m.checked_mul(size.$axis as $unsigned));)+
+                measure.expect("Measure overflowed")
+            }
+
+            pub fn contains(&self, point: &Point) -> bool {
+                point.in_bounds(self.min, self.max)
+            }
+
+            pub fn contains_box(&self, other: &$name) -> bool {
+                self.contains(&other.min) && self.contains(&other.max)
+            }
+
+            pub fn intersects(&self, other: &$name) -> bool {
+                true $(&& self.min.$axis <= other.max.$axis && other.min.$axis <= self.max.$axis)+
+            }
+
+            pub fn intersection(&self, other: &$name) -> Option<$name> {
+                if !self.intersects(other) { return None; }
+                Some($name {
+                    min: Point { $($axis: std::cmp::max(self.min.$axis, other.min.$axis)),+ },
+                    max: Point { $($axis: std::cmp::min(self.max.$axis, other.max.$axis)),+ },
+                })
+            }
+
+            // The smallest box containing both boxes
+            pub fn union(&self, other: &$name) -> $name {
+                $name {
+                    min: Point { $($axis: std::cmp::min(self.min.$axis, other.min.$axis)),+ },
+                    max: Point { $($axis: std::cmp::max(self.max.$axis, other.max.$axis)),+ },
+                }
+            }
+
+            // The point in the box closest to the given point
+            pub fn clamp(&self, point: &Point) -> Point {
+                Point { $($axis: point.$axis.clamp(self.min.$axis, self.max.$axis)),+ }
+            }
 
-        fn add(self, vec: &Vector) -> Point {
-            point(self.x + vec.x, self.y + vec.y)
+            // Manhattan distance from the given point to the nearest point in the box
+            pub fn distance_to(&self, point: &Point) -> $unsigned {
+                (*point - self.clamp(point)).grid_len()
+            }
+
+            // Halves the box along each axis longer than one point, e.g. an octree subdivision for
+            // 3D boxes. The lower half is the smaller of the two if the length is odd.
+            pub fn split(&self) -> Vec<$name> {
+                let mut boxes = vec!(*self);
+                $(
+                    boxes = boxes.into_iter().flat_map(|b| {
+                        // One less than the length, which (unlike the length) always fits in the unsigned type
+                        let span = (b.max.$axis as $unsigned).wrapping_sub(b.min.$axis as $unsigned);
+                        if span == 0 { return vec!(b); }
+                        let mid = b.min.$axis.checked_add_unsigned(span / 2 + span % 2).expect("Within the box");
+                        let (mut lower, mut upper) = (b, b);
+                        lower.max.$axis = mid - 1;
+                        upper.min.$axis = mid;
+                        vec!(lower, upper)
+                    }).collect();
+                )+
+                boxes
+            }
+
+            // Every point in the box, iterating the first axis fastest (i.e. reading order in 2D)
+            #[allow(unused_assignments)] // remaining isn't needed after the last axis
+            pub fn points(&self) -> impl Iterator<Item = Point> {
+                let (min, size) = (self.min, self.size());
+                (0..self.$measure()).map(move |i| {
+                    let mut remaining = i;
+                    Point { $($axis: {
+                        let len = size.$axis as $unsigned;
+                        let coord = min.$axis + (remaining % len) as $scalar;
+                        remaining /= len;
+                        coord
+                    }),+ }
+                })
+            }
+        }
+
+        // Parses inclusive bounds formatted like "min..max", e.g. "(1, 2)..(3, 4)"
+        impl std::str::FromStr for $name {
+            type Err = crate::error::ParseError;
+
+            fn from_str(s: &str) -> Result<Self, crate::error::ParseError> {
+                let malformed = || crate::error::ParseError::Malformed(s.to_string());
+                let mut parts = s.splitn(2, "..");
+                let min: Point = parts.next().ok_or_else(malformed)?.trim().parse()?;
+                let max: Point = parts.next().ok_or_else(malformed)?.trim().parse()?;
+                if true $(&& min.$axis <= max.$axis)+ {
+                    Ok($name::new(min, max))
+                } else {
+                    Err(malformed())
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "{}..{}", self.min, self.max)
+            }
         }
     }
+}
+
+// Defines a ManhattanBall type over the Point and Vector types in scope, i.e. all the points within
+// some Manhattan (L1) distance of a center point. In 2D this is a diamond, in 3D an octahedron. If
+// a box type defined by euclid_box!() is specified, e.g. manhattan_ball!(Rect; x, y), the ball can
+// also be tested against boxes of that type.
+macro_rules! manhattan_ball {
+    ($($axis:ident),+) => { manhattan_ball!(i32, u32; $($axis),+); };
+    ($box:ident; $($axis:ident),+) => { manhattan_ball!($box, i32, u32; $($axis),+); };
+    ($box:ident, $scalar:ty, $unsigned:ty; $($axis:ident),+) => {
+        manhattan_ball!($scalar, $unsigned; $($axis),+);
 
-    impl Add<&Vector> for &Point {
-        type Output = Point;
+        #[allow(dead_code)]
+        impl ManhattanBall {
+            pub fn intersects_box(&self, bounds: &$box) -> bool {
+                bounds.distance_to(&self.center) <= self.radius
+            }
 
-        fn add(self, vec: &Vector) -> Point {
-            point(self.x + vec.x, self.y + vec.y)
+            // Since the ball is convex it contains the box if it contains all of the box's corners
+            pub fn contains_box(&self, bounds: &$box) -> bool {
+                let (min, max) = (bounds.min(), bounds.max());
+                // The corner furthest from the center along each axis
+                let furthest = Point { $($axis: if self.center.$axis - min.$axis > max.$axis - self.center.$axis {
+                    min.$axis } else { max.$axis }),+ };
+                self.contains(&furthest)
+            }
+        }
+    };
+    ($scalar:ty, $unsigned:ty; $($axis:ident),+) => {
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+        pub struct ManhattanBall {
+            center: Point,
+            radius: $unsigned,
         }
+
+        // Not every space uses every operation
+        #[allow(dead_code)]
+        impl ManhattanBall {
+            pub fn new(center: Point, radius: $unsigned) -> ManhattanBall {
+                ManhattanBall { center, radius }
+            }
+
+            pub fn center(&self) -> Point { self.center }
+            pub fn radius(&self) -> $unsigned { self.radius }
+
+            pub fn contains(&self, point: &Point) -> bool {
+                (*point - self.center).grid_len() <= self.radius
+            }
+
+            pub fn contains_ball(&self, other: &ManhattanBall) -> bool {
+                other.radius <= self.radius
+                    && (other.center - self.center).grid_len() <= self.radius - other.radius
+            }
+
+            // Whether the balls share a point. Walking from one center to the other along the axes
+            // passes through every distance in between, so (unlike with the Euclidean metric) two
+            // balls on the integer lattice intersect exactly when their radii cover the distance.
+            pub fn intersects(&self, other: &ManhattanBall) -> bool {
+                // If the radii sum overflows it covers any distance
+                self.radius.checked_add(other.radius)
+                    .is_none_or(|reach| (other.center - self.center).grid_len() <= reach)
+            }
+
+            // Manhattan distance from the given point to the nearest point in the ball
+            pub fn distance_to(&self, point: &Point) -> $unsigned {
+                (*point - self.center).grid_len().saturating_sub(self.radius)
+            }
+
+            // The point in the ball closest to the origin; if several are equally close the one
+            // reached by moving along the earlier axes first is returned
+            #[allow(unused_assignments)] // remaining isn't needed after the last axis
+            pub fn closest_to_origin(&self) -> Point {
+                let mut remaining = self.radius;
+                Point { $($axis: {
+                    let coord = self.center.$axis;
+                    let step = std::cmp::min(coord.unsigned_abs(), remaining);
+                    remaining -= step;
+                    coord - coord.signum() * step as $scalar
+                }),+ }
+            }
+        }
+
+        impl std::fmt::Display for ManhattanBall {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "{} r={}", self.center, self.radius)
+            }
+        }
+    };
+}
+
+euclid_space!(x, y);
+euclid_box!(Rect, area; x, y);
+manhattan_ball!(Rect; x, y);
+
+// For puzzles whose coordinates don't comfortably fit in an i32
+pub mod wide {
+    euclid_space!(i64, u64; x, y);
+    euclid_box!(Rect, area, i64, u64; x, y);
+    manhattan_ball!(Rect, i64, u64; x, y);
+}
+
+// The helpers below are 2D-only. As in most puzzle maps y increases downwards, so North is (0, -1).
+
+mod direction {
+    use super::{Vector, vector};
+    use std::str::FromStr;
+    use crate::error::ParseError;
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+    pub enum Direction {
+        North,
+        East,
+        South,
+        West,
     }
 
-    impl Add<Vector> for &Point {
-        type Output = Point;
+    impl Direction {
+        // Clockwise, starting from North
+        pub const ALL: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
 
-        fn add(self, vec: Vector) -> Point {
-            point(self.x + vec.x, self.y + vec.y)
+        // Accepts compass points (NSEW), arrows (^v<>) or up/down/left/right (UDLR)
+        pub fn from_char(c: char) -> Result<Direction, ParseError> {
+            match c {
+                'N' | '^' | 'U' => Ok(Direction::North),
+                'E' | '>' | 'R' => Ok(Direction::East),
+                'S' | 'v' | 'D' => Ok(Direction::South),
+                'W' | '<' | 'L' => Ok(Direction::West),
+                _ => Err(ParseError::Malformed(format!("direction '{}'", c))),
+            }
+        }
+
+        pub fn as_vector(self) -> Vector {
+            match self {
+                Direction::North => vector(0, -1),
+                Direction::East => vector(1, 0),
+                Direction::South => vector(0, 1),
+                Direction::West => vector(-1, 0),
+            }
+        }
+
+        pub fn arrow(self) -> char {
+            match self {
+                Direction::North => '^',
+                Direction::East => '>',
+                Direction::South => 'v',
+                Direction::West => '<',
+            }
+        }
+
+        pub fn left(self) -> Direction {
+            Direction::ALL[(self as usize + 3) % 4]
+        }
+
+        pub fn right(self) -> Direction {
+            Direction::ALL[(self as usize + 1) % 4]
+        }
+
+        #[allow(dead_code)]
+        pub fn reverse(self) -> Direction {
+            Direction::ALL[(self as usize + 2) % 4]
         }
     }
 
-    impl Add<Vector> for Point {
-        type Output = Point;
+    impl FromStr for Direction {
+        type Err = ParseError;
 
-        fn add(self, vec: Vector) -> Point {
-            point(self.x + vec.x, self.y + vec.y)
+        fn from_str(s: &str) -> Result<Self, ParseError> {
+            let mut chars = s.trim().chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Direction::from_char(c),
+                _ => Err(ParseError::Malformed(format!("direction '{}'", s))),
+            }
         }
     }
+}
+pub use self::direction::Direction;
+
+mod neighborhood {
+    use super::{Point, Vector, vector};
 
-    impl AddAssign<Vector> for Point {
-        fn add_assign(&mut self, vec: Vector) {
-            *self = point(self.x + vec.x, self.y + vec.y);
+    // In reading order, i.e. top-to-bottom, left-to-right
+    static NEIGHBORS4: [Vector; 4] = [vector(0, -1), vector(-1, 0), vector(1, 0), vector(0, 1)];
+    static NEIGHBORS8: [Vector; 8] = [
+        vector(-1, -1), vector(0, -1), vector(1, -1),
+        vector(-1, 0), vector(1, 0),
+        vector(-1, 1), vector(0, 1), vector(1, 1)];
+
+    impl Point {
+        // Orthogonally adjacent points, in reading order
+        pub fn neighbors4(self) -> impl Iterator<Item = Point> {
+            NEIGHBORS4.iter().map(move |v| self + v)
+        }
+
+        // Orthogonally and diagonally adjacent points, in reading order
+        pub fn neighbors8(self) -> impl Iterator<Item = Point> {
+            NEIGHBORS8.iter().map(move |v| self + v)
         }
     }
+}
 
-    impl Sub for Point {
-        type Output = Vector;
+// Dense, row-major storage for a rectangular region of the plane. Its bounds needn't start at the
+// origin.
+mod grid {
+    use super::{Point, Rect, point, vector};
+    use std::ops::{Index, IndexMut};
+    use crate::error::ParseError;
 
-        fn sub(self, point: Point) -> Vector { vector(self.x - point.x, self.y - point.y) }
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub struct Grid<T> {
+        cells: Vec<T>,
+        bounds: Rect,
     }
 
-    impl Sub for &Point {
-        type Output = Vector;
+    impl<T: Clone> Grid<T> {
+        // A width x height grid with its top-left corner at the origin
+        pub fn new(width: usize, height: usize, fill: T) -> Grid<T> {
+            Grid::with_bounds(Rect::with_size(Point::ORIGIN, vector(width as i32, height as i32)), fill)
+        }
 
-        fn sub(self, point: &Point) -> Vector { vector(self.x - point.x, self.y - point.y) }
+        pub fn with_bounds(bounds: Rect, fill: T) -> Grid<T> {
+            Grid { cells: vec![fill; bounds.area() as usize], bounds }
+        }
     }
 
-    impl FromStr for Point {
-        type Err = ParseError;
+    impl<T> Grid<T> {
+        pub fn from_fn(bounds: Rect, f: impl Fn(Point) -> T) -> Grid<T> {
+            Grid { cells: bounds.points().map(f).collect(), bounds }
+        }
 
-        fn from_str(s: &str) -> Result<Self, ParseError> {
-            lazy_static! {
-                // r"^([^,]+),([^,]+)$" would be more strict - worth it?
-                static ref RE: Regex = Regex::new(r"^\(?([^(,]+),([^),]+)\)?$").unwrap();
+        // The cells must be in reading order
+        pub fn from_vec(bounds: Rect, cells: Vec<T>) -> Grid<T> {
+            assert_eq!(cells.len(), bounds.area() as usize, "Wrong number of cells for {}", bounds);
+            Grid { cells, bounds }
+        }
+
+        // Parses a rectangle of characters, with the top-left character at the origin
+        pub fn from_ascii(s: &str, f: impl Fn(char) -> Option<T>) -> Result<Grid<T>, ParseError> {
+            let lines: Vec<_> = s.lines().collect();
+            let width = lines.first().map(|l| l.chars().count()).unwrap_or(0);
+            if width == 0 {
+                return Err(ParseError::Malformed("empty grid".into()));
+            }
+            let mut cells = Vec::with_capacity(width * lines.len());
+            for (y, line) in lines.iter().enumerate() {
+                if line.chars().count() != width {
+                    return Err(ParseError::Malformed(
+                        format!("row {} has length {}, expected {}", y, line.chars().count(), width)));
+                }
+                for (x, c) in line.chars().enumerate() {
+                    let cell = f(c).ok_or_else(|| ParseError::Malformed(
+                        format!("'{}' at {}", c, point(x as i32, y as i32))))?;
+                    cells.push(cell);
+                }
+            }
+            Ok(Grid { cells, bounds: Rect::with_size(Point::ORIGIN, vector(width as i32, lines.len() as i32)) })
+        }
+
+        pub fn bounds(&self) -> Rect { self.bounds }
+        pub fn width(&self) -> usize { self.bounds.size().x as usize }
+        pub fn height(&self) -> usize { self.bounds.size().y as usize }
+
+        // Hot, so avoids the overflow checks and assertions of Rect::contains() and Point - Point
+        fn offset(&self, coord: &Point) -> Option<usize> {
+            let (min, max) = (self.bounds.min(), self.bounds.max());
+            if coord.x < min.x || coord.y < min.y || coord.x > max.x || coord.y > max.y {
+                return None;
+            }
+            let width = (max.x - min.x) as usize + 1;
+            Some((coord.y - min.y) as usize * width + (coord.x - min.x) as usize)
+        }
+
+        pub fn get(&self, coord: &Point) -> Option<&T> {
+            self.offset(coord).map(|i| &self.cells[i])
+        }
+
+        pub fn get_mut(&mut self, coord: &Point) -> Option<&mut T> {
+            self.offset(coord).map(move |i| &mut self.cells[i])
+        }
+
+        // All cells in reading order
+        pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+            self.bounds.points().zip(self.cells.iter())
+        }
+
+        #[allow(dead_code)]
+        pub fn iter_mut(&mut self) -> impl Iterator<Item = (Point, &mut T)> {
+            self.bounds.points().zip(self.cells.iter_mut())
+        }
+
+        #[allow(dead_code)]
+        pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+            self.cells.chunks(self.width())
+        }
+
+        #[allow(dead_code)]
+        pub fn row(&self, y: i32) -> Option<&[T]> {
+            let start = self.offset(&point(self.bounds.min().x, y))?;
+            Some(&self.cells[start..start + self.width()])
+        }
+
+        #[allow(dead_code)]
+        pub fn column(&self, x: i32) -> impl Iterator<Item = &T> {
+            let start = self.offset(&point(x, self.bounds.min().y));
+            // An out-of-bounds column is empty
+            let cells = if start.is_some() { &self.cells[..] } else { &[] };
+            cells.iter().skip(start.unwrap_or(0)).step_by(self.width())
+        }
+
+        // The cells within the given rect, in reading order
+        #[allow(dead_code)]
+        pub fn region(&self, rect: &Rect) -> impl Iterator<Item = (Point, &T)> {
+            rect.intersection(&self.bounds).into_iter()
+                .flat_map(|r| r.points())
+                .map(move |p| (p, &self[p]))
+        }
+
+        // Orthogonally adjacent cells, in reading order
+        #[allow(dead_code)]
+        pub fn neighbors4(&self, coord: Point) -> impl Iterator<Item = (Point, &T)> {
+            coord.neighbors4().filter_map(move |p| self.get(&p).map(|v| (p, v)))
+        }
+
+        // Orthogonally and diagonally adjacent cells, in reading order
+        #[allow(dead_code)]
+        pub fn neighbors8(&self, coord: Point) -> impl Iterator<Item = (Point, &T)> {
+            coord.neighbors8().filter_map(move |p| self.get(&p).map(|v| (p, v)))
+        }
+
+        #[allow(dead_code)]
+        pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+            Grid { cells: self.cells.iter().map(f).collect(), bounds: self.bounds }
+        }
+
+        #[allow(dead_code)]
+        pub fn zip<U, V>(&self, other: &Grid<U>, f: impl Fn(&T, &U) -> V) -> Grid<V> {
+            assert_eq!(self.bounds, other.bounds, "Grids must be the same shape");
+            Grid {
+                cells: self.cells.iter().zip(other.cells.iter()).map(|(a, b)| f(a, b)).collect(),
+                bounds: self.bounds,
             }
+        }
 
-            let caps = regex_captures!(RE, s)?;
-            let x: i32 = capture_group!(caps, 1).trim().parse()?;
-            let y: i32 = capture_group!(caps, 2).trim().parse()?;
-            return Ok(point(x, y));
+        // One line per row, e.g. for use in a Display impl
+        #[allow(dead_code)]
+        pub fn render(&self, f: impl Fn(&T) -> char) -> String {
+            let mut out = String::with_capacity((self.width() + 1) * self.height());
+            for row in self.rows() {
+                out.extend(row.iter().map(&f));
+                out.push('\n');
+            }
+            out
         }
     }
 
-    impl fmt::Debug for Point {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "({}, {})", self.x, self.y)
+    impl<T> Index<Point> for Grid<T> {
+        type Output = T;
+
+        fn index(&self, coord: Point) -> &T {
+            self.get(&coord).unwrap_or_else(|| panic!("{} is outside {}", coord, self.bounds))
         }
     }
 
-    impl fmt::Display for Point {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "{:?}", self)
+    impl<T> IndexMut<Point> for Grid<T> {
+        fn index_mut(&mut self, coord: Point) -> &mut T {
+            let bounds = self.bounds;
+            self.get_mut(&coord).unwrap_or_else(|| panic!("{} is outside {}", coord, bounds))
         }
     }
 
@@ -127,123 +777,708 @@ mod point {
     mod tests {
         use super::*;
 
+        fn counting(width: usize, height: usize) -> Grid<i32> {
+            let mut grid = Grid::new(width, height, 0);
+            for (i, (_, v)) in grid.iter_mut().enumerate() {
+                *v = i as i32;
+            }
+            grid
+        }
+
         #[test]
-        fn parse() {
-            assert_eq!("3, 4".parse::<Point>(), Ok(point(3, 4)));
-            assert_eq!("-3,-4".parse::<Point>(), Ok(point(-3, -4)));
-            assert_eq!("(40,30)".parse::<Point>(), Ok(point(40, 30)));
-            assert_eq!("(-3, -5)".parse::<Point>(), Ok(point(-3, -5)));
+        fn index() {
+            let mut grid = Grid::new(5, 5, 0);
+            assert_eq!(grid[point(2, 2)], 0);
+            grid[point(2, 2)] += 1;
+            assert_eq!(grid[point(2, 2)], 1);
+            assert_eq!(grid.get(&point(5, 0)), None);
+            assert_eq!(grid.get(&point(-1, 1)), None);
+        }
+
+        #[test]
+        #[should_panic(expected = "outside")]
+        fn index_out_of_bounds() {
+            let _ = Grid::new(2, 2, 0)[point(2, 0)];
+        }
 
-            assert!("abc".parse::<Point>().is_err());
+        #[test]
+        fn offset_origin() {
+            let bounds = Rect::new(point(-2, 5), point(0, 6));
+            let grid = Grid::from_fn(bounds, |p| p.x * p.y);
+            assert_eq!((grid.width(), grid.height()), (3, 2));
+            assert_eq!(grid[point(-2, 6)], -12);
+            assert_eq!(grid.get(&point(0, 0)), None);
+            assert_eq!(grid.row(5), Some(&[-10, -5, 0][..]));
+            assert_eq!(grid.column(-1).collect::<Vec<_>>(), vec!(&-5, &-6));
+        }
+
+        #[test]
+        fn iteration() {
+            let grid = Grid::new(2, 2, 0);
+            let mut grid_iter = grid.iter();
+            assert_eq!(grid_iter.next().unwrap(), (point(0, 0), &0));
+            assert_eq!(grid_iter.next().unwrap(), (point(1, 0), &0));
+            assert_eq!(grid_iter.next().unwrap(), (point(0, 1), &0));
+            assert_eq!(grid_iter.next().unwrap(), (point(1, 1), &0));
+            assert_eq!(grid_iter.next(), None);
         }
 
         #[test]
-        fn bounding() {
-            let points = vec!(point(1, 2), point(2, 3), point(0, 5));
-            assert_eq!(Point::bounding_box(points), Some((point(0, 2), point(2, 5))));
+        fn rows_and_columns() {
+            let grid = counting(3, 2);
+            assert_eq!(grid.rows().collect::<Vec<_>>(), vec!(&[0, 1, 2][..], &[3, 4, 5][..]));
+            assert_eq!(grid.column(1).collect::<Vec<_>>(), vec!(&1, &4));
+            assert_eq!(grid.column(3).count(), 0);
+            assert_eq!(grid.row(2), None);
         }
 
         #[test]
-        fn in_bounds_() {
-            let zero_zero = point(0, 0);
-            let two_two = point(2, 2);
-            let five_six = point(5, 6);
-            assert!(two_two.in_bounds(zero_zero, two_two));
-            assert!(!five_six.in_bounds(zero_zero, two_two));
+        fn region() {
+            let grid = counting(3, 3);
+            let region: Vec<_> = grid.region(&Rect::new(point(1, 1), point(5, 5))).map(|(_, v)| *v).collect();
+            assert_eq!(region, vec!(4, 5, 7, 8));
+            assert_eq!(grid.region(&Rect::new(point(5, 5), point(6, 6))).count(), 0);
         }
 
         #[test]
-        fn add() {
-            assert_eq!(point(1, 0) + super::super::vector(2, 3), point(3, 3));
+        fn neighbors() {
+            let grid = counting(3, 3);
+            assert_eq!(grid.neighbors4(point(0, 0)).map(|(_, v)| *v).collect::<Vec<_>>(), vec!(1, 3));
+            assert_eq!(grid.neighbors8(point(1, 1)).count(), 8);
+            assert_eq!(grid.neighbors8(point(2, 2)).map(|(_, v)| *v).collect::<Vec<_>>(), vec!(4, 5, 7));
         }
+
         #[test]
-        fn sub() {
-            assert_eq!(point(3, 3) - point(1, 0), super::super::vector(2, 3));
+        fn transforms() {
+            let grid = counting(2, 2);
+            let doubled = grid.map(|v| v * 2);
+            assert_eq!(doubled[point(1, 1)], 6);
+            let summed = grid.zip(&doubled, |a, b| a + b);
+            assert_eq!(summed.iter().map(|(_, v)| *v).collect::<Vec<_>>(), vec!(0, 3, 6, 9));
+        }
+
+        #[test]
+        fn ascii() {
+            let art = "#.#\n..#\n";
+            let grid = Grid::from_ascii(art, |c| match c { '#' => Some(true), '.' => Some(false), _ => None }).unwrap();
+            assert_eq!((grid.width(), grid.height()), (3, 2));
+            assert!(grid[point(2, 1)]);
+            assert_eq!(grid.render(|&b| if b { '#' } else { '.' }), art);
+
+            assert_eq!(Grid::from_ascii("#.\n#", Some),
+                       Err(ParseError::Malformed("row 1 has length 1, expected 2".into())));
+            assert_eq!(Grid::from_ascii("#.\n#x", |c| if c == 'x' { None } else { Some(c) }),
+                       Err(ParseError::Malformed("'x' at (1, 1)".into())));
         }
     }
 }
-pub use self::point::{Point,point};
+pub use self::grid::Grid;
 
-mod vector {
-    use std::fmt;
-    use std::str::FromStr;
-    use crate::error::ParseError;
+// A map over the whole (infinite) plane, storing only the cells that have been set. Tracks the
+// bounds of every point written so far, and optionally has a default value for unset cells.
+mod sparse_grid {
+    use super::{Grid, Point, Rect, point};
+    use std::collections::HashMap;
+    use std::ops::{Index, IndexMut};
+
+    // Cells are stored in CHUNK x CHUNK blocks, so dense-ish regions don't need a hash entry per
+    // point while empty regions take up no space at all.
+    const CHUNK: i32 = 16;
 
-    #[derive(Copy, Clone, PartialEq, Eq, Hash)]
-    pub struct Vector {
-        pub x: i32,
-        pub y: i32,
+    #[derive(Clone, Debug)]
+    pub struct SparseGrid<T> {
+        chunks: HashMap<Point, Vec<Option<T>>>,
+        default: Option<T>,
+        bounds: Option<Rect>,
+        len: usize,
     }
 
-    #[inline]
-    pub const fn vector(x: i32, y: i32) -> Vector {
-        Vector { x, y }
+    fn chunk_and_offset(coord: &Point) -> (Point, usize) {
+        let chunk = point(coord.x.div_euclid(CHUNK), coord.y.div_euclid(CHUNK));
+        let offset = coord.y.rem_euclid(CHUNK) * CHUNK + coord.x.rem_euclid(CHUNK);
+        (chunk, offset as usize)
     }
 
-    impl Vector {
-        pub fn abs(&self) -> Vector {
-            vector(self.x.abs(), self.y.abs())
+    impl<T> SparseGrid<T> {
+        // Indexing unset cells of a grid without a default panics
+        pub fn new() -> SparseGrid<T> {
+            SparseGrid { chunks: HashMap::new(), default: None, bounds: None, len: 0 }
         }
 
-        pub fn len(&self) -> f64 {
-            (self.x as f64).hypot(self.y as f64)
+        #[allow(dead_code)]
+        pub fn with_default(default: T) -> SparseGrid<T> {
+            SparseGrid { default: Some(default), ..SparseGrid::new() }
         }
 
-        pub fn grid_len(&self) -> u32 {
-            (self.x.abs() + self.y.abs()) as u32
+        // Bounds of every point set (or passed to expand_bounds()), which never shrink
+        pub fn bounds(&self) -> Option<Rect> { self.bounds }
+
+        pub fn expand_bounds(&mut self, coord: Point) {
+            let cell = Rect::new(coord, coord);
+            self.bounds = Some(self.bounds.map_or(cell, |b| b.union(&cell)));
+        }
+
+        // The number of set cells
+        #[allow(dead_code)]
+        pub fn len(&self) -> usize { self.len }
+        #[allow(dead_code)]
+        pub fn is_empty(&self) -> bool { self.len == 0 }
+
+        pub fn get(&self, coord: &Point) -> Option<&T> {
+            let (chunk, offset) = chunk_and_offset(coord);
+            self.chunks.get(&chunk).and_then(|c| c[offset].as_ref())
+        }
+
+        pub fn get_mut(&mut self, coord: &Point) -> Option<&mut T> {
+            let (chunk, offset) = chunk_and_offset(coord);
+            self.chunks.get_mut(&chunk).and_then(|c| c[offset].as_mut())
+        }
+
+        pub fn insert(&mut self, coord: Point, value: T) -> Option<T> {
+            self.expand_bounds(coord);
+            let (chunk, offset) = chunk_and_offset(&coord);
+            let chunk = self.chunks.entry(chunk)
+                .or_insert_with(|| (0..CHUNK * CHUNK).map(|_| None).collect());
+            let prior = chunk[offset].replace(value);
+            if prior.is_none() { self.len += 1; }
+            prior
+        }
+
+        #[allow(dead_code)]
+        pub fn remove(&mut self, coord: &Point) -> Option<T> {
+            let (chunk, offset) = chunk_and_offset(coord);
+            let prior = self.chunks.get_mut(&chunk).and_then(|c| c[offset].take());
+            if prior.is_some() { self.len -= 1; }
+            prior
+        }
+
+        // Set cells, in no particular order
+        pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+            self.chunks.iter().flat_map(|(chunk, cells)| {
+                cells.iter().enumerate().filter_map(move |(i, cell)| {
+                    let i = i as i32;
+                    cell.as_ref().map(|v| (point(chunk.x * CHUNK + i % CHUNK, chunk.y * CHUNK + i / CHUNK), v))
+                })
+            })
+        }
+
+        // Every point within the bounds, set or not, in reading order
+        pub fn points(&self) -> impl Iterator<Item = Point> {
+            self.bounds.into_iter().flat_map(|b| b.points())
+        }
+
+        // The points along the edge of the bounds. Anything reachable from these points is
+        // potentially unbounded, since the plane extends past them.
+        pub fn border(&self) -> impl Iterator<Item = Point> {
+            let points: Vec<_> = self.points().filter(|p| self.on_border(p)).collect();
+            points.into_iter()
+        }
+
+        pub fn on_border(&self, coord: &Point) -> bool {
+            match self.bounds {
+                Some(b) => b.contains(coord) &&
+                    (coord.x == b.min().x || coord.x == b.max().x || coord.y == b.min().y || coord.y == b.max().y),
+                None => false,
+            }
+        }
+
+        // One line per row of the bounds; f is passed None for unset cells
+        #[allow(dead_code)]
+        pub fn render(&self, f: impl Fn(Option<&T>) -> char) -> String {
+            let mut out = String::new();
+            if let Some(bounds) = self.bounds {
+                for p in bounds.points() {
+                    out.push(f(self.get(&p)));
+                    if p.x == bounds.max().x { out.push('\n'); }
+                }
+            }
+            out
         }
     }
 
-    impl FromStr for Vector {
-        type Err = ParseError;
+    impl<T: Clone> SparseGrid<T> {
+        // Unset cells within the bounds are filled with the default. Returns None if the grid is
+        // empty, or if it has no default and any cell within the bounds is unset.
+        #[allow(dead_code)]
+        pub fn to_dense(&self) -> Option<Grid<T>> {
+            let bounds = self.bounds?;
+            let size = bounds.size();
+            if self.default.is_none() && self.len < size.x as usize * size.y as usize {
+                return None;
+            }
+            Some(Grid::from_fn(bounds, |p| self[p].clone()))
+        }
 
-        fn from_str(s: &str) -> Result<Self, ParseError> {
-            // Just reuse point's parser
-            let p: super::Point = s.parse()?;
-            Ok(vector(p.x, p.y))
+        // Only cells that differ from the default are stored, but the bounds match the grid's
+        #[allow(dead_code)]
+        pub fn from_dense(grid: &Grid<T>, default: T) -> SparseGrid<T> where T: PartialEq {
+            let mut sparse = SparseGrid::with_default(default);
+            sparse.expand_bounds(grid.bounds().min());
+            sparse.expand_bounds(grid.bounds().max());
+            for (p, v) in grid.iter() {
+                if Some(v) != sparse.default.as_ref() {
+                    sparse.insert(p, v.clone());
+                }
+            }
+            sparse
         }
     }
 
-    impl fmt::Debug for Vector {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "({}, {})", self.x, self.y)
+    impl<T> Default for SparseGrid<T> {
+        fn default() -> Self { SparseGrid::new() }
+    }
+
+    impl<T> Index<Point> for SparseGrid<T> {
+        type Output = T;
+
+        fn index(&self, coord: Point) -> &T {
+            self.get(&coord).or(self.default.as_ref())
+                .unwrap_or_else(|| panic!("{} is unset and there is no default", coord))
         }
     }
 
-    impl fmt::Display for Vector {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "{:?}", self)
+    // Unset cells are initialized with the default
+    impl<T: Clone> IndexMut<Point> for SparseGrid<T> {
+        fn index_mut(&mut self, coord: Point) -> &mut T {
+            if self.get(&coord).is_none() {
+                let default = self.default.clone()
+                    .unwrap_or_else(|| panic!("{} is unset and there is no default", coord));
+                self.insert(coord, default);
+            }
+            self.get_mut(&coord).expect("Just inserted")
         }
     }
 
     #[cfg(test)]
     mod tests {
-        use super::super::point;
         use super::*;
 
+        #[test]
+        fn insert() {
+            let mut grid = SparseGrid::new();
+            assert_eq!(grid.bounds(), None);
+            assert_eq!(grid.insert(point(1, 2), 10), None);
+            assert_eq!(grid.bounds(), Some(Rect::new(point(1, 2), point(1, 2))));
+            assert_eq!(grid.insert(point(-30, 40), 5), None);
+            assert_eq!(grid.insert(point(1, 2), 11), Some(10));
+            assert_eq!(grid.bounds(), Some(Rect::new(point(-30, 2), point(1, 40))));
+            assert_eq!(grid.len(), 2);
+            assert_eq!(grid.get(&point(-30, 40)), Some(&5));
+            assert_eq!(grid.get(&point(-29, 40)), None);
+
+            let mut cells: Vec<_> = grid.iter().map(|(p, v)| (p, *v)).collect();
+            cells.sort();
+            assert_eq!(cells, vec!((point(-30, 40), 5), (point(1, 2), 11)));
+
+            assert_eq!(grid.remove(&point(1, 2)), Some(11));
+            assert_eq!(grid.len(), 1);
+            assert_eq!(grid.bounds(), Some(Rect::new(point(-30, 2), point(1, 40))));
+        }
+
+        #[test]
+        fn defaults() {
+            let mut grid = SparseGrid::with_default(0);
+            assert_eq!(grid[point(1_000_000, -1_000_000)], 0);
+            assert!(grid.is_empty());
+            grid[point(-1, -1)] += 3;
+            assert_eq!(grid[point(-1, -1)], 3);
+            assert_eq!(grid.len(), 1);
+        }
+
+        #[test]
+        #[should_panic(expected = "no default")]
+        fn no_default() {
+            let grid: SparseGrid<i32> = SparseGrid::new();
+            let _ = grid[point(0, 0)];
+        }
+
+        #[test]
+        fn border() {
+            let mut grid = SparseGrid::new();
+            grid.insert(point(0, 0), 'a');
+            grid.insert(point(2, 2), 'b');
+            assert_eq!(grid.border().count(), 8);
+            assert!(!grid.on_border(&point(1, 1)));
+            assert!(grid.on_border(&point(2, 1)));
+            assert!(!grid.on_border(&point(3, 1)));
+        }
+
+        #[test]
+        fn dense() {
+            let mut grid = SparseGrid::with_default('.');
+            grid.insert(point(-1, 0), '#');
+            grid.insert(point(1, 1), '#');
+            let dense = grid.to_dense().unwrap();
+            assert_eq!(dense.render(|&c| c), "#..\n..#\n");
+            assert_eq!(grid.render(|c| *c.unwrap_or(&' ')), "#  \n  #\n");
+
+            let sparse = SparseGrid::from_dense(&dense, '.');
+            assert_eq!(sparse.len(), 2);
+            assert_eq!(sparse.bounds(), grid.bounds());
+            assert_eq!(sparse.to_dense(), Some(dense));
+        }
+
+        #[test]
+        fn dense_without_default() {
+            let mut grid = SparseGrid::new();
+            assert_eq!(grid.to_dense(), None);
+            grid.insert(point(0, 0), 'a');
+            grid.insert(point(1, 1), 'b');
+            assert_eq!(grid.to_dense(), None);
+            grid.insert(point(1, 0), 'c');
+            grid.insert(point(0, 1), 'd');
+            assert_eq!(grid.to_dense().map(|g| g.render(|&c| c)), Some("ac\ndb\n".to_string()));
+        }
+    }
+}
+pub use self::sparse_grid::SparseGrid;
+
+// Declarative reader for ASCII-art maps, where each character is either terrain or an entity (e.g.
+// a unit or cart) standing on top of some terrain. For example:
+//   AsciiMap::new().terrain('#', Wall).terrain('.', Open).entity('E', Elf, Open).parse(input)
+mod ascii_map {
+    use super::{Grid, Point, Rect, point, vector};
+    use std::collections::HashMap;
+    use crate::error::ParseError;
+
+    pub struct AsciiMap<T, E> {
+        terrain: HashMap<char, T>,
+        entities: HashMap<char, (E, T)>,
+        padding: Option<T>,
+    }
+
+    impl<T: Clone, E: Clone> AsciiMap<T, E> {
+        pub fn new() -> AsciiMap<T, E> {
+            AsciiMap { terrain: HashMap::new(), entities: HashMap::new(), padding: None }
+        }
+
+        pub fn terrain(mut self, c: char, terrain: T) -> AsciiMap<T, E> {
+            self.terrain.insert(c, terrain);
+            self
+        }
+
+        pub fn entity(mut self, c: char, entity: E, underlying: T) -> AsciiMap<T, E> {
+            self.entities.insert(c, (entity, underlying));
+            self
+        }
+
+        // Allows lines shorter than the longest line, filling them out with the given terrain.
+        // Otherwise every line must be the same length.
+        pub fn padding(mut self, terrain: T) -> AsciiMap<T, E> {
+            self.padding = Some(terrain);
+            self
+        }
+
+        // Returns the terrain, with the top-left character at the origin, and the entities in
+        // reading order.
+        #[allow(clippy::type_complexity)]
+        pub fn parse(&self, s: &str) -> Result<(Grid<T>, Vec<(Point, E)>), ParseError> {
+            let lines: Vec<Vec<char>> = s.lines().map(|l| l.chars().collect()).collect();
+            let width = match &self.padding {
+                Some(_) => lines.iter().map(|l| l.len()).max().unwrap_or(0),
+                None => lines.first().map(|l| l.len()).unwrap_or(0),
+            };
+            if width == 0 {
+                return Err(ParseError::Malformed("empty map".into()));
+            }
+
+            let mut cells = Vec::with_capacity(width * lines.len());
+            let mut entities = Vec::new();
+            for (y, line) in lines.iter().enumerate() {
+                if self.padding.is_none() && line.len() != width {
+                    return Err(ParseError::Malformed(
+                        format!("row {} has length {}, expected {}", y, line.len(), width)));
+                }
+                for (x, c) in line.iter().enumerate() {
+                    if let Some(terrain) = self.terrain.get(c) {
+                        cells.push(terrain.clone());
+                    } else if let Some((entity, terrain)) = self.entities.get(c) {
+                        cells.push(terrain.clone());
+                        entities.push((point(x as i32, y as i32), entity.clone()));
+                    } else {
+                        return Err(ParseError::Malformed(format!("'{}' at {}", c, point(x as i32, y as i32))));
+                    }
+                }
+                if let Some(padding) = &self.padding {
+                    cells.extend(std::iter::repeat_n(padding, width - line.len()).cloned());
+                }
+            }
+            let bounds = Rect::with_size(Point::ORIGIN, vector(width as i32, lines.len() as i32));
+            Ok((Grid::from_vec(bounds, cells), entities))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        enum Terrain { Wall, Open }
+
+        fn reader() -> AsciiMap<Terrain, char> {
+            AsciiMap::new()
+                .terrain('#', Terrain::Wall).terrain('.', Terrain::Open)
+                .entity('E', 'e', Terrain::Open).entity('G', 'g', Terrain::Open)
+        }
+
         #[test]
         fn parse() {
-            assert_eq!("3, 4".parse::<Vector>(), Ok(vector(3, 4)));
-            assert_eq!("-3,-4".parse::<Vector>(), Ok(vector(-3, -4)));
+            let (terrain, entities) = reader().parse("####\n#G.#\n#.E#\n####\n").unwrap();
+            assert_eq!((terrain.width(), terrain.height()), (4, 4));
+            assert_eq!(terrain[point(0, 0)], Terrain::Wall);
+            assert_eq!(terrain[point(1, 1)], Terrain::Open);
+            assert_eq!(terrain[point(2, 2)], Terrain::Open);
+            assert_eq!(entities, vec!((point(1, 1), 'g'), (point(2, 2), 'e')));
+        }
+
+        #[test]
+        fn errors() {
+            assert_eq!(reader().parse("###\n#x#\n").unwrap_err(),
+                       ParseError::Malformed("'x' at (1, 1)".into()));
+            assert_eq!(reader().parse("###\n##\n").unwrap_err(),
+                       ParseError::Malformed("row 1 has length 2, expected 3".into()));
+            assert!(reader().parse("").is_err());
         }
 
         #[test]
-        fn len() {
-            assert_eq!(vector(3, -4).len(), 5_f64);
+        fn padding() {
+            let (terrain, entities) = reader().padding(Terrain::Wall).parse("###\n#E\n#").unwrap();
+            assert_eq!(terrain.render(|t| if *t == Terrain::Wall { '#' } else { '.' }), "###\n#.#\n###\n");
+            assert_eq!(entities, vec!((point(1, 1), 'e')));
+        }
+    }
+}
+pub use self::ascii_map::AsciiMap;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_point() {
+        assert_eq!("3, 4".parse::<Point>(), Ok(point(3, 4)));
+        assert_eq!("-3,-4".parse::<Point>(), Ok(point(-3, -4)));
+        assert_eq!("(40,30)".parse::<Point>(), Ok(point(40, 30)));
+        assert_eq!("(-3, -5)".parse::<Point>(), Ok(point(-3, -5)));
+
+        assert!("abc".parse::<Point>().is_err());
+        assert!("1, 2, 3".parse::<Point>().is_err());
+    }
+
+    #[test]
+    fn bounding() {
+        let points = vec!(point(1, 2), point(2, 3), point(0, 5));
+        assert_eq!(Point::bounding_box(points), Some((point(0, 2), point(2, 5))));
+    }
+
+    #[test]
+    fn in_bounds_() {
+        let zero_zero = point(0, 0);
+        let two_two = point(2, 2);
+        let five_six = point(5, 6);
+        assert!(two_two.in_bounds(zero_zero, two_two));
+        assert!(!five_six.in_bounds(zero_zero, two_two));
+    }
+
+    #[test]
+    fn add() {
+        assert_eq!(point(1, 0) + vector(2, 3), point(3, 3));
+        assert_eq!(vector(1, 0) + vector(2, 3), vector(3, 3));
+    }
+
+    #[test]
+    fn sub() {
+        assert_eq!(point(3, 3) - point(1, 0), vector(2, 3));
+        assert_eq!(point(3, 3) - vector(1, 0), point(2, 3));
+    }
+
+    #[test]
+    fn scale() {
+        assert_eq!(vector(1, -2) * 3, vector(3, -6));
+        assert_eq!(-vector(1, -2), vector(-1, 2));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(point(1, -2).to_string(), "(1, -2)");
+        assert_eq!(format!("{:?}", vector(3, 4)), "(3, 4)");
+    }
+
+    #[test]
+    fn parse_vector() {
+        assert_eq!("3, 4".parse::<Vector>(), Ok(vector(3, 4)));
+        assert_eq!("-3,-4".parse::<Vector>(), Ok(vector(-3, -4)));
+    }
+
+    #[test]
+    fn len() {
+        assert_eq!(vector(3, -4).len(), 5_f64);
+        assert_eq!(vector(3, -4).chebyshev_len(), 4);
+    }
+
+    #[test]
+    fn checked() {
+        let max = point(i32::MAX, 0);
+        assert_eq!(max.checked_add(&vector(1, 0)), None);
+        assert_eq!(max.checked_add(&vector(-1, 1)), Some(point(i32::MAX - 1, 1)));
+        assert_eq!(point(i32::MIN, 0).checked_sub(&point(1, 0)), None);
+        assert_eq!(vector(i32::MIN, 0).checked_neg(), None);
+        assert_eq!(vector(i32::MAX, 1).checked_mul(2), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Point overflowed")]
+    fn overflow() {
+        let _ = point(i32::MAX, 0) + vector(1, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Length overflowed")]
+    fn length_overflow() {
+        vector(i32::MIN, i32::MIN).grid_len();
+    }
+
+    #[test]
+    fn wide() {
+        let min = wide::point(i32::MIN as i64, i32::MIN as i64);
+        assert_eq!((wide::Point::ORIGIN - min).grid_len(), 1 << 32);
+        assert_eq!("(10000000000, -1)".parse::<wide::Point>(), Ok(wide::point(10_000_000_000, -1)));
+    }
+
+    #[test]
+    fn directions() {
+        for (s, expected) in &[("N", Direction::North), ("v", Direction::South), ("R", Direction::East)] {
+            assert_eq!(s.parse::<Direction>(), Ok(*expected));
         }
+        assert!("X".parse::<Direction>().is_err());
+        assert!("NE".parse::<Direction>().is_err());
 
-        parameterized_test::create!{ grid_lens, (p1, p2, d), {
-            assert_eq!((p1 - p2).grid_len(), d);
-            assert_eq!((p2 - p1).grid_len(), d);
-        }}
-        grid_lens! {
-            a: (point(1,1), point(1,1), 0),
-            b: (point(1,1), point(1,2), 1),
-            c: (point(1,1), point(2,2), 2),
-            d: (point(1,1), point(1,5), 4),
-            e: (point(1,1), point(8,3), 9),
-            f: (point(1,1), point(-1,-1), 4),
+        for &dir in &Direction::ALL {
+            assert_eq!(Direction::from_char(dir.arrow()), Ok(dir));
+            assert_eq!(dir.left().right(), dir);
+            assert_eq!(dir.left().left(), dir.reverse());
+            assert_eq!(dir.reverse().as_vector(), -dir.as_vector());
         }
+        assert_eq!(Direction::North.right(), Direction::East);
+        assert_eq!(Direction::North.left(), Direction::West);
+    }
+
+    #[test]
+    fn neighbors() {
+        assert_eq!(point(1, 1).neighbors4().collect::<Vec<_>>(),
+                   vec!(point(1, 0), point(0, 1), point(2, 1), point(1, 2)));
+        let neighbors: Vec<_> = point(1, 1).neighbors8().collect();
+        let mut sorted = neighbors.clone();
+        sorted.sort_by_key(|p| (p.y, p.x));
+        assert_eq!(neighbors, sorted);
+        assert_eq!(neighbors.len(), 8);
+        assert!(neighbors.iter().all(|&p| (p - point(1, 1)).chebyshev_len() == 1));
+    }
+
+    #[test]
+    fn rect() {
+        let rect = Rect::with_size(point(1, 2), vector(3, 2));
+        assert_eq!(rect, Rect::new(point(1, 2), point(3, 3)));
+        assert_eq!(rect.size(), vector(3, 2));
+        assert_eq!(rect.area(), 6);
+        assert!(rect.contains(&point(3, 3)));
+        assert!(!rect.contains(&point(4, 3)));
+        assert_eq!(rect.points().collect::<Vec<_>>(),
+                   vec!(point(1, 2), point(2, 2), point(3, 2), point(1, 3), point(2, 3), point(3, 3)));
+        assert_eq!(Rect::bounding(vec!(point(3, 3), point(1, 2))), Some(rect));
+    }
+
+    #[test]
+    fn rect_set_operations() {
+        let a = Rect::new(point(0, 0), point(3, 3));
+        let b = Rect::new(point(2, 1), point(5, 2));
+        let c = Rect::new(point(4, 4), point(5, 5));
+        assert_eq!(a.intersection(&b), Some(Rect::new(point(2, 1), point(3, 2))));
+        assert_eq!(a.intersection(&c), None);
+        assert!(!b.intersects(&c));
+        assert_eq!(a.union(&c), Rect::new(point(0, 0), point(5, 5)));
+        assert!(a.union(&c).contains_box(&b));
+        assert!(!a.contains_box(&b));
+        assert_eq!(c.distance_to(&point(0, 0)), 8);
+        assert_eq!(a.distance_to(&point(1, 1)), 0);
+    }
+
+    #[test]
+    fn manhattan_ball() {
+        let ball = ManhattanBall::new(point(2, 3), 2);
+        let points = Rect::new(point(-1, -1), point(6, 6)).points().filter(|p| ball.contains(p)).count();
+        assert_eq!(points, 13);
+        assert!(ball.contains(&point(3, 4)));
+        assert!(!ball.contains(&point(4, 4)));
+        assert_eq!(ball.distance_to(&point(6, 6)), 5);
+        assert_eq!(ball.distance_to(&point(2, 2)), 0);
+        assert_eq!(ball.closest_to_origin(), point(0, 3));
+        assert_eq!(ManhattanBall::new(point(-2, 1), 5).closest_to_origin(), point(0, 0));
+        assert_eq!(ManhattanBall::new(point(-1, -3), 2).closest_to_origin(), point(0, -2));
+
+        assert!(ball.intersects(&ManhattanBall::new(point(5, 5), 3)));
+        assert!(!ball.intersects(&ManhattanBall::new(point(5, 5), 2)));
+        assert!(ball.intersects(&ManhattanBall::new(point(100, 100), u32::MAX)));
+        assert!(ManhattanBall::new(point(0, 0), u32::MAX).intersects(&ManhattanBall::new(point(5, 0), 10)));
+        assert!(ball.contains_ball(&ManhattanBall::new(point(3, 3), 1)));
+        assert!(!ball.contains_ball(&ManhattanBall::new(point(4, 3), 1)));
+
+        assert!(!ball.intersects_box(&Rect::new(point(4, 4), point(5, 5))));
+        assert!(ball.intersects_box(&Rect::new(point(3, 4), point(5, 5))));
+        assert!(ball.contains_box(&Rect::new(point(1, 3), point(3, 3))));
+        assert!(ball.contains_box(&Rect::new(point(1, 2), point(3, 3))));
+        assert!(!ball.contains_box(&Rect::new(point(0, 2), point(3, 3))));
+        assert_eq!(ball.to_string(), "(2, 3) r=2");
+    }
+
+    #[test]
+    fn rect_split() {
+        let rect = Rect::new(point(0, 0), point(3, 2));
+        assert_eq!(rect.split(), vec!(
+            Rect::new(point(0, 0), point(1, 0)), Rect::new(point(0, 1), point(1, 2)),
+            Rect::new(point(2, 0), point(3, 0)), Rect::new(point(2, 1), point(3, 2))));
+        assert_eq!(Rect::new(point(0, 0), point(0, 1)).split().len(), 2);
+        assert_eq!(Rect::new(point(0, 0), point(0, 0)).split().len(), 1);
+
+        // The length of the whole range doesn't fit in an i32 or u32
+        let full = Rect::new(point(i32::MIN, 0), point(i32::MAX, 0));
+        assert_eq!(full.split(), vec!(Rect::new(point(i32::MIN, 0), point(-1, 0)), Rect::new(point(0, 0), point(i32::MAX, 0))));
+        let full = Rect::new(point(i32::MIN, i32::MIN), point(i32::MAX, i32::MAX));
+        assert_eq!(full.split().len(), 4);
+    }
+
+    #[test]
+    fn rect_with_size_at_limits() {
+        assert_eq!(Rect::with_size(point(i32::MAX, i32::MIN), vector(1, i32::MAX)),
+                   Rect::new(point(i32::MAX, i32::MIN), point(i32::MAX, -2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Box overflowed")]
+    fn rect_with_size_overflow() {
+        Rect::with_size(point(i32::MAX, 0), vector(2, 1));
+    }
+
+    #[test]
+    fn parse_rect() {
+        let rect = Rect::new(point(-1, 2), point(3, 4));
+        assert_eq!("(-1, 2)..(3, 4)".parse::<Rect>(), Ok(rect));
+        assert_eq!("-1,2..3,4".parse::<Rect>(), Ok(rect));
+        assert_eq!(rect.to_string().parse::<Rect>(), Ok(rect));
+        assert!("(3, 4)..(-1, 2)".parse::<Rect>().is_err());
+        assert!("(3, 4)".parse::<Rect>().is_err());
+    }
+
+    parameterized_test::create!{ grid_lens, (p1, p2, d), {
+        assert_eq!((p1 - p2).grid_len(), d);
+        assert_eq!((p2 - p1).grid_len(), d);
+    }}
+    grid_lens! {
+        a: (point(1,1), point(1,1), 0),
+        b: (point(1,1), point(1,2), 1),
+        c: (point(1,1), point(2,2), 2),
+        d: (point(1,1), point(1,5), 4),
+        e: (point(1,1), point(8,3), 9),
+        f: (point(1,1), point(-1,-1), 4),
     }
 }
-pub use self::vector::{Vector,vector};
@@ -0,0 +1,369 @@
+// Dependency-free image output, for maps that are too large to usefully print. Supports the binary
+// PPM (color) and PGM (grayscale) Netpbm formats as well as uncompressed 24-bit BMPs, which most
+// image viewers can open directly.
+//   http://netpbm.sourceforge.net/doc/ppm.html
+//   https://en.wikipedia.org/wiki/BMP_file_format
+
+// Returns the FILE argument following --render, if present
+pub fn render_path(args: &[String]) -> Option<&str> {
+    args.iter().position(|a| a == "--render")
+        .map(|i| args.get(i+1).expect("--render requires a FILE").as_str())
+}
+
+mod color {
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+    pub struct Color {
+        pub r: u8,
+        pub g: u8,
+        pub b: u8,
+    }
+
+    impl Color {
+        pub const BLACK: Color = Color::rgb(0, 0, 0);
+        pub const WHITE: Color = Color::rgb(255, 255, 255);
+
+        pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+            Color { r, g, b }
+        }
+
+        pub const fn gray(v: u8) -> Color {
+            Color::rgb(v, v, v)
+        }
+
+        // Perceived brightness, per ITU-R BT.601
+        pub fn luma(&self) -> u8 {
+            ((self.r as u32 * 299 + self.g as u32 * 587 + self.b as u32 * 114) / 1000) as u8
+        }
+
+        // A fully-saturated color with the given hue, in degrees
+        pub fn hue(degrees: u32) -> Color {
+            let degrees = degrees % 360;
+            let rising = (degrees % 60 * 255 / 60) as u8;
+            let falling = 255 - rising;
+            match degrees / 60 {
+                0 => Color::rgb(255, rising, 0),
+                1 => Color::rgb(falling, 255, 0),
+                2 => Color::rgb(0, 255, rising),
+                3 => Color::rgb(0, falling, 255),
+                4 => Color::rgb(rising, 0, 255),
+                _ => Color::rgb(255, 0, falling),
+            }
+        }
+    }
+}
+pub use self::color::Color;
+
+mod palette {
+    use super::Color;
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    // Maps cell types (e.g. an enum, or the characters of a text rendering) to colors
+    pub struct Palette<T> {
+        colors: HashMap<T, Color>,
+        default: Color,
+    }
+
+    impl<T: Eq + Hash> Palette<T> {
+        // Anything not otherwise in the palette will be drawn in the default color
+        pub fn new(default: Color) -> Palette<T> {
+            Palette { colors: HashMap::new(), default }
+        }
+
+        // Assigns each key a different, evenly spaced, hue
+        pub fn distinct(keys: impl IntoIterator<Item = T>, default: Color) -> Palette<T> {
+            let keys: Vec<_> = keys.into_iter().collect();
+            let step = 360 / keys.len().max(1) as u32;
+            let colors = keys.into_iter().enumerate().map(|(i, k)| (k, Color::hue(i as u32 * step))).collect();
+            Palette { colors, default }
+        }
+
+        pub fn color(mut self, key: T, color: Color) -> Palette<T> {
+            self.colors.insert(key, color);
+            self
+        }
+
+        pub fn get(&self, key: &T) -> Color {
+            *self.colors.get(key).unwrap_or(&self.default)
+        }
+
+        pub fn default_color(&self) -> Color {
+            self.default
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn lookup() {
+            let palette = Palette::new(Color::BLACK).color('#', Color::WHITE);
+            assert_eq!(palette.get(&'#'), Color::WHITE);
+            assert_eq!(palette.get(&'.'), Color::BLACK);
+        }
+
+        #[test]
+        fn distinct() {
+            let palette = Palette::distinct(vec!('a', 'b', 'c'), Color::BLACK);
+            assert_eq!(palette.get(&'a'), Color::rgb(255, 0, 0));
+            assert_eq!(palette.get(&'b'), Color::rgb(0, 255, 0));
+            assert_eq!(palette.get(&'c'), Color::rgb(0, 0, 255));
+            assert_eq!(palette.get(&'d'), Color::BLACK);
+        }
+    }
+}
+pub use self::palette::Palette;
+
+mod bitmap {
+    use super::{Color, Palette};
+    use std::fs::File;
+    use std::io::{self, BufWriter, Write};
+    use std::path::Path;
+    use crate::euclid::{Grid, SparseGrid};
+
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub struct Image {
+        width: usize,
+        height: usize,
+        pixels: Vec<Color>,
+    }
+
+    impl Image {
+        pub fn new(width: usize, height: usize, background: Color) -> Image {
+            Image { width, height, pixels: vec![background; width * height] }
+        }
+
+        #[allow(dead_code)]
+        pub fn from_grid<T>(grid: &Grid<T>, f: impl Fn(&T) -> Color) -> Image {
+            let pixels = grid.iter().map(|(_, c)| f(c)).collect();
+            Image { width: grid.width(), height: grid.height(), pixels }
+        }
+
+        // Draws every point within the grid's bounds, including cells that are unset
+        pub fn from_sparse<T>(grid: &SparseGrid<T>, f: impl Fn(Option<&T>) -> Color) -> Image {
+            match grid.bounds() {
+                Some(bounds) => {
+                    let size = bounds.size();
+                    let pixels = grid.points().map(|p| f(grid.get(&p))).collect();
+                    Image { width: size.x as usize, height: size.y as usize, pixels }
+                },
+                None => Image::new(0, 0, Color::BLACK),
+            }
+        }
+
+        // Colors an existing text rendering; short lines are filled with the palette's default
+        pub fn from_text(text: &str, palette: &Palette<char>) -> Image {
+            let lines: Vec<_> = text.lines().collect();
+            let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+            let mut image = Image::new(width, lines.len(), palette.default_color());
+            for (y, line) in lines.iter().enumerate() {
+                for (x, c) in line.chars().enumerate() {
+                    image.set(x, y, palette.get(&c));
+                }
+            }
+            image
+        }
+
+        #[allow(dead_code)]
+        pub fn width(&self) -> usize { self.width }
+        #[allow(dead_code)]
+        pub fn height(&self) -> usize { self.height }
+
+        pub fn get(&self, x: usize, y: usize) -> Color {
+            assert!(x < self.width && y < self.height, "({}, {}) is outside the image", x, y);
+            self.pixels[y * self.width + x]
+        }
+
+        pub fn set(&mut self, x: usize, y: usize, color: Color) {
+            assert!(x < self.width && y < self.height, "({}, {}) is outside the image", x, y);
+            self.pixels[y * self.width + x] = color;
+        }
+
+        // Enlarges each pixel to a factor x factor square, since one pixel per cell is often tiny
+        pub fn scale(&self, factor: usize) -> Image {
+            let mut image = Image::new(self.width * factor, self.height * factor, Color::BLACK);
+            for y in 0..image.height {
+                for x in 0..image.width {
+                    image.set(x, y, self.get(x / factor, y / factor));
+                }
+            }
+            image
+        }
+
+        pub fn write_ppm(&self, out: &mut impl Write) -> io::Result<()> {
+            write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+            let bytes: Vec<u8> = self.pixels.iter().flat_map(|c| vec!(c.r, c.g, c.b)).collect();
+            out.write_all(&bytes)
+        }
+
+        pub fn write_pgm(&self, out: &mut impl Write) -> io::Result<()> {
+            write!(out, "P5\n{} {}\n255\n", self.width, self.height)?;
+            let bytes: Vec<u8> = self.pixels.iter().map(Color::luma).collect();
+            out.write_all(&bytes)
+        }
+
+        // 24 bits per pixel, with no compression or color table. Rows are stored bottom-to-top, in
+        // BGR order, and padded to a multiple of four bytes.
+        pub fn write_bmp(&self, out: &mut impl Write) -> io::Result<()> {
+            const HEADER_SIZE: u32 = 14 + 40;
+            let row_size = (self.width * 3).div_ceil(4) * 4;
+            let data_size = (row_size * self.height) as u32;
+
+            // File header
+            out.write_all(b"BM")?;
+            out.write_all(&(HEADER_SIZE + data_size).to_le_bytes())?;
+            out.write_all(&0_u32.to_le_bytes())?; // reserved
+            out.write_all(&HEADER_SIZE.to_le_bytes())?;
+            // BITMAPINFOHEADER
+            out.write_all(&40_u32.to_le_bytes())?;
+            out.write_all(&(self.width as i32).to_le_bytes())?;
+            out.write_all(&(self.height as i32).to_le_bytes())?;
+            out.write_all(&1_u16.to_le_bytes())?; // color planes
+            out.write_all(&24_u16.to_le_bytes())?; // bits per pixel
+            out.write_all(&0_u32.to_le_bytes())?; // no compression
+            out.write_all(&data_size.to_le_bytes())?;
+            out.write_all(&2835_i32.to_le_bytes())?; // 72 DPI, in pixels per meter
+            out.write_all(&2835_i32.to_le_bytes())?;
+            out.write_all(&0_u32.to_le_bytes())?; // colors in the (absent) color table
+            out.write_all(&0_u32.to_le_bytes())?; // all colors are important
+
+            let mut row = Vec::with_capacity(row_size);
+            for y in (0..self.height).rev() {
+                row.clear();
+                for c in &self.pixels[y * self.width..(y + 1) * self.width] {
+                    row.extend_from_slice(&[c.b, c.g, c.r]);
+                }
+                row.resize(row_size, 0);
+                out.write_all(&row)?;
+            }
+            Ok(())
+        }
+
+        // Picks the format based on the file's extension
+        pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+            let path = path.as_ref();
+            let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+            let writer = match extension.as_deref() {
+                Some("ppm") => Image::write_ppm,
+                Some("pgm") => Image::write_pgm,
+                Some("bmp") => Image::write_bmp,
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                               format!("Unsupported image format: {}", path.display()))),
+            };
+            let mut out = BufWriter::new(File::create(path)?);
+            writer(self, &mut out)?;
+            out.flush()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::euclid::point;
+
+        fn checkerboard() -> Image {
+            let mut image = Image::new(2, 2, Color::BLACK);
+            image.set(1, 0, Color::WHITE);
+            image.set(0, 1, Color::rgb(255, 0, 0));
+            image
+        }
+
+        #[test]
+        fn ppm() {
+            let mut out = Vec::new();
+            checkerboard().write_ppm(&mut out).unwrap();
+            let mut expected = b"P6\n2 2\n255\n".to_vec();
+            expected.extend_from_slice(&[0, 0, 0, 255, 255, 255, 255, 0, 0, 0, 0, 0]);
+            assert_eq!(out, expected);
+        }
+
+        #[test]
+        fn pgm() {
+            let mut out = Vec::new();
+            checkerboard().write_pgm(&mut out).unwrap();
+            let mut expected = b"P5\n2 2\n255\n".to_vec();
+            expected.extend_from_slice(&[0, 255, 76, 0]);
+            assert_eq!(out, expected);
+        }
+
+        #[test]
+        fn bmp() {
+            let mut out = Vec::new();
+            checkerboard().write_bmp(&mut out).unwrap();
+            // Two rows of 6 bytes, each padded to 8
+            assert_eq!(out.len(), 54 + 16);
+            assert_eq!(&out[0..2], b"BM");
+            assert_eq!(&out[2..6], &70_u32.to_le_bytes());
+            // Bottom row first, in BGR order
+            assert_eq!(&out[54..], &[0, 0, 255, 0, 0, 0, 0, 0, 0, 0, 0, 255, 255, 255, 0, 0]);
+        }
+
+        #[test]
+        fn from_text() {
+            let palette = Palette::new(Color::BLACK).color('#', Color::WHITE);
+            let image = Image::from_text("#.\n.#\n#", &palette);
+            assert_eq!((image.width(), image.height()), (2, 3));
+            assert_eq!(image.get(0, 0), Color::WHITE);
+            assert_eq!(image.get(1, 0), Color::BLACK);
+            assert_eq!(image.get(1, 1), Color::WHITE);
+            assert_eq!(image.get(1, 2), Color::BLACK);
+        }
+
+        #[test]
+        fn from_grids() {
+            let mut sparse = SparseGrid::new();
+            sparse.insert(point(-1, 3), true);
+            sparse.insert(point(1, 4), false);
+            let color = |c: Option<&bool>| match c {
+                Some(true) => Color::WHITE,
+                Some(false) => Color::gray(128),
+                None => Color::BLACK,
+            };
+            let image = Image::from_sparse(&sparse, color);
+            assert_eq!((image.width(), image.height()), (3, 2));
+            assert_eq!(image.get(0, 0), Color::WHITE);
+            assert_eq!(image.get(2, 1), Color::gray(128));
+            assert_eq!(image.get(1, 1), Color::BLACK);
+
+            let dense = Grid::from_fn(sparse.bounds().unwrap(), |p| sparse.get(&p).cloned());
+            assert_eq!(Image::from_grid(&dense, |c| color(c.as_ref())), image);
+        }
+
+        #[test]
+        fn scale() {
+            let image = checkerboard().scale(3);
+            assert_eq!((image.width(), image.height()), (6, 6));
+            assert_eq!(image.get(2, 2), Color::BLACK);
+            assert_eq!(image.get(3, 2), Color::WHITE);
+            assert_eq!(image.get(2, 3), Color::rgb(255, 0, 0));
+        }
+
+        #[test]
+        fn unknown_format() {
+            assert!(checkerboard().save("image.png").is_err());
+        }
+    }
+}
+pub use self::bitmap::Image;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_args() {
+        let args: Vec<String> = vec!("--render".into(), "out.ppm".into());
+        assert_eq!(render_path(&args), Some("out.ppm"));
+        assert_eq!(render_path(&args[1..]), None);
+    }
+
+    #[test]
+    fn hues() {
+        assert_eq!(Color::hue(0), Color::rgb(255, 0, 0));
+        assert_eq!(Color::hue(60), Color::rgb(255, 255, 0));
+        assert_eq!(Color::hue(180), Color::rgb(0, 255, 255));
+        assert_eq!(Color::hue(270), Color::rgb(127, 0, 255));
+        assert_eq!(Color::luma(&Color::WHITE), 255);
+    }
+}
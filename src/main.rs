@@ -1,6 +1,6 @@
 // https://github.com/rust-lang/cargo/issues/3591#issuecomment-475701083
 //#![ allow( dead_code, unused_imports, unused_variables ) ]
-#[macro_use] extern crate itertools;
+extern crate itertools;
 #[macro_use] extern crate lazy_static;
 extern crate parameterized_test;
 extern crate chrono;
@@ -20,8 +20,11 @@ macro_rules! capture_group {
 
 mod error;
 mod device;
-mod euclid;
+#[macro_use] mod euclid;
 mod euclid3d;
+mod animation;
+mod image;
+mod automaton;
 mod pathfinding;
 
 mod aoc1;
@@ -64,19 +67,19 @@ fn main() {
         3 => aoc3::advent(),
         4 => aoc4::advent(),
         5 => aoc5::advent(),
-        6 => aoc6::advent(),
-        7 => aoc7::advent(),
+        6 => aoc6::advent(&args[2..]),
+        7 => aoc7::advent(&args[2..]),
         8 => aoc8::advent(),
         9 => aoc9::advent(&args[2..]),
-        10 => aoc10::advent(),
+        10 => aoc10::advent(&args[2..]),
         11 => aoc11::advent(),
-        12 => aoc12::advent(),
-        13 => aoc13::advent(),
+        12 => aoc12::advent(&args[2..]),
+        13 => aoc13::advent(&args[2..]),
         14 => aoc14::advent(),
-        15 => aoc15::advent(),
+        15 => aoc15::advent(&args[2..]),
         16 => aoc16::advent(),
-        17 => aoc17::advent(),
-        18 => aoc18::advent(),
+        17 => aoc17::advent(&args[2..]),
+        18 => aoc18::advent(&args[2..]),
         19 => aoc19::advent(),
         20 => aoc20::advent(),
         21 => aoc21::advent(&args[2..]),
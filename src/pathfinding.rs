@@ -1,6 +1,8 @@
 mod pathfinding {
-    use std::collections::{VecDeque, HashMap, BinaryHeap};
-    use std::cmp::Ordering;
+    use std::collections::{VecDeque, HashMap, HashSet, BinaryHeap};
+    use std::cmp::{self, Ordering, Reverse};
+    use std::fmt;
+    use super::{Cost, SearchTrace};
 
     // References:
     // https://www.redblobgames.com/pathfinding/a-star/introduction.html
@@ -8,21 +10,35 @@ mod pathfinding {
     // https://doc.rust-lang.org/std/collections/binary_heap/
     pub trait Graph {
         type Node: Clone + std::fmt::Debug + Eq + core::hash::Hash;
+        type Cost: Cost;
 
-        fn neighbors(&self, source: &Self::Node) -> Vec<Edge<Self::Node>>;
+        fn neighbors(&self, source: &Self::Node) -> Vec<Edge<Self::Node, Self::Cost>>;
 
+        // Edge weights are ignored, but must all be the same
+        #[allow(dead_code)]
         fn bfs(&self, start: &Self::Node, goal: &Self::Node) -> Option<Vec<Self::Node>> {
+            self.bfs_traced(start, goal, &mut SearchTrace::disabled())
+        }
+
+        #[allow(dead_code)]
+        fn bfs_traced(&self, start: &Self::Node, goal: &Self::Node, trace: &mut SearchTrace<Self::Node>)
+                -> Option<Vec<Self::Node>> {
             let mut frontier = VecDeque::new();
             frontier.push_back(start.clone());
             let mut routes = HashMap::new();
             routes.insert(start.clone(), start.clone()); // careful, potential infinite loop
+            let mut uniform_weight = None;
 
             while ! frontier.is_empty() && !routes.contains_key(goal) {
+                trace.frontier(frontier.iter());
                 let current = frontier.pop_front().expect("frontier is not empty");
+                trace.visit(&current);
                 for edge in self.neighbors(&current) {
-                    assert_eq!(edge.weight(), 1, "BFS does not support weighted edges: {:?}", edge);
+                    let weight = *uniform_weight.get_or_insert(edge.weight());
+                    assert!(edge.weight() == weight, "BFS does not support weighted edges: {:?}", edge);
                     let next = edge.dest();
                     if !routes.contains_key(next) {
+                        trace.reach(&current, next);
                         frontier.push_back(next.clone());
                         routes.insert(next.clone(), current.clone());
                     }
@@ -36,109 +52,1046 @@ mod pathfinding {
                     path.push(current);
                     current = next.clone();
                 } else {
+                    trace.finish(None);
                     return None;
                 }
             }
             path.push(start.clone());
             path.reverse();
+            trace.finish(Some(&path));
             Some(path)
         }
 
-        fn dijkstras(&self, start: &Self::Node, goal: &Self::Node) -> Option<Vec<Edge<Self::Node>>> {
+        // Edge weights must be non-negative, use bellman_ford() for graphs with negative edges
+        fn dijkstras(&self, start: &Self::Node, goal: &Self::Node) -> Option<Route<Self::Node, Self::Cost>> {
+            self.dijkstras_traced(start, goal, &mut SearchTrace::disabled())
+        }
+
+        fn dijkstras_traced(&self, start: &Self::Node, goal: &Self::Node, trace: &mut SearchTrace<Self::Node>)
+                -> Option<Route<Self::Node, Self::Cost>> {
+            self.a_star_traced(start, goal, |_| Self::Cost::zero(), trace)
+        }
+
+        // The heuristic must never overestimate the remaining cost to the goal (and should never
+        // decrease by more than an edge's weight), otherwise the route may not be optimal.
+        #[allow(dead_code)]
+        fn a_star<H>(&self, start: &Self::Node, goal: &Self::Node, heuristic: H) -> Option<Route<Self::Node, Self::Cost>>
+                where H: Fn(&Self::Node) -> Self::Cost {
+            self.a_star_traced(start, goal, heuristic, &mut SearchTrace::disabled())
+        }
+
+        fn a_star_traced<H>(&self, start: &Self::Node, goal: &Self::Node, heuristic: H,
+                            trace: &mut SearchTrace<Self::Node>) -> Option<Route<Self::Node, Self::Cost>>
+                where H: Fn(&Self::Node) -> Self::Cost {
+            let zero = Self::Cost::zero();
             let mut frontier = BinaryHeap::new();
             let mut costs = HashMap::new();
             let mut routes = HashMap::new();
-            frontier.push(State { cost: 0, node: start.clone() });
-            costs.insert(start.clone(), 0);
+            frontier.push(State { cost: heuristic(start), node: start.clone() });
+            costs.insert(start.clone(), zero);
             routes.insert(start.clone(),
-                          Edge::new(0, start.clone(), start.clone())); // careful, potential infinite loop
+                          Edge::new(zero, start.clone(), start.clone())); // careful, potential infinite loop
 
-            while let Some(State { cost: _, node: current }) = frontier.pop() {
-                if routes.contains_key(goal) { break; }
+            loop {
+                trace.frontier(frontier.iter().map(|s| &s.node));
+                let State { cost, node: current } = match frontier.pop() {
+                    Some(state) => state,
+                    None => break,
+                };
+                // The goal's cost isn't final until it's popped; a cheaper route may still be queued
+                if current == *goal { break; }
                 let current_cost = *costs.get(&current).expect("Current cost should be known");
+                // stale entry, already expanded more cheaply
+                if current_cost.plus(heuristic(&current)) < cost { continue; }
+                trace.visit(&current);
                 for edge in self.neighbors(&current) {
+                    assert!(edge.weight().partial_cmp(&zero) != Some(Ordering::Less),
+                            "Dijkstra's does not support negative edges: {:?}", edge);
                     let next = edge.dest();
-                    let next_cost = current_cost + edge.weight();
+                    let next_cost = current_cost.plus(edge.weight());
 
-                    let prior_next_cost = costs.get(&next);
+                    let prior_next_cost = costs.get(next);
                     if prior_next_cost.is_none() || *prior_next_cost.expect("Not-none") > next_cost {
+                        trace.reach(&current, next);
                         costs.insert(next.clone(), next_cost);
-                        frontier.push(State { cost: next_cost, node: next.clone() });
+                        frontier.push(State { cost: next_cost.plus(heuristic(next)), node: next.clone() });
                         routes.insert(next.clone(), edge.clone());
                     }
                 }
             }
 
-            let mut path = Vec::new();
-            let mut current = goal.clone();
-            while current != *start {
-                if let Some(next) = routes.get(&current) {
-                    path.push(next.clone());
-                    current = next.source().clone();
+            let route = walk_routes(&routes, start, goal);
+            match route {
+                Some(ref route) => {
+                    let path: Vec<_> = route.iter().map(|e| e.source().clone())
+                        .chain(std::iter::once(goal.clone())).collect();
+                    trace.finish(Some(&path));
+                },
+                None => trace.finish(None),
+            }
+            route
+        }
+
+        // Memory-bounded alternative to a_star(), re-searching depth-first with an increasing cost
+        // bound instead of remembering every node it's seen. Only terminates on unreachable goals
+        // if the graph is finite.
+        // https://en.wikipedia.org/wiki/Iterative_deepening_A*
+        #[allow(dead_code)]
+        fn ida_star<H>(&self, start: &Self::Node, goal: &Self::Node, heuristic: H) -> Option<Route<Self::Node, Self::Cost>>
+                where H: Fn(&Self::Node) -> Self::Cost {
+            if start == goal { return Some(Vec::new()); }
+            let mut bound = heuristic(start);
+            loop {
+                // The smallest estimate that exceeded the current bound, i.e. the next bound
+                let mut next_bound: Option<Self::Cost> = None;
+                let mut path: Route<Self::Node, Self::Cost> = Vec::new();
+                let mut on_path: HashSet<_> = [start.clone()].iter().cloned().collect();
+                let mut costs = vec!(Self::Cost::zero());
+                let mut work = vec!(self.neighbors(start));
+
+                while let Some(edges) = work.last_mut() {
+                    let edge = match edges.pop() {
+                        Some(edge) => edge,
+                        None => {
+                            work.pop();
+                            if let Some(edge) = path.pop() {
+                                on_path.remove(edge.dest());
+                                costs.pop();
+                            }
+                            continue;
+                        }
+                    };
+                    if on_path.contains(edge.dest()) { continue; }
+                    let cost = costs.last().expect("Not empty").plus(edge.weight());
+                    let estimate = cost.plus(heuristic(edge.dest()));
+                    if estimate > bound {
+                        if next_bound.is_none_or(|b| estimate < b) {
+                            next_bound = Some(estimate);
+                        }
+                        continue;
+                    }
+                    let found = edge.dest() == goal;
+                    on_path.insert(edge.dest().clone());
+                    costs.push(cost);
+                    work.push(self.neighbors(edge.dest()));
+                    path.push(edge);
+                    if found { return Some(path); }
+                }
+
+                bound = next_bound?;
+            }
+        }
+
+        // Supports negative edge weights, but is O(V*E) rather than Dijkstra's O(E + V log V), and
+        // the graph reachable from start must be finite.
+        // Returns an error if a negative cycle is reachable from the start, since no path through
+        // it has a well-defined minimum cost.
+        // https://en.wikipedia.org/wiki/Bellman%E2%80%93Ford_algorithm
+        #[allow(clippy::type_complexity)]
+        #[allow(dead_code)]
+        fn bellman_ford(&self, start: &Self::Node, goal: &Self::Node)
+                -> Result<Option<Route<Self::Node, Self::Cost>>, NegativeCycle<Self::Node>> {
+            // Unlike Dijkstra's we need every (reachable) edge up front
+            let mut edges = Vec::new();
+            let mut seen = HashSet::new();
+            let mut frontier = VecDeque::new();
+            seen.insert(start.clone());
+            frontier.push_back(start.clone());
+            while let Some(current) = frontier.pop_front() {
+                for edge in self.neighbors(&current) {
+                    if seen.insert(edge.dest().clone()) {
+                        frontier.push_back(edge.dest().clone());
+                    }
+                    edges.push(edge);
+                }
+            }
+
+            let mut costs = HashMap::new();
+            let mut routes = HashMap::new();
+            costs.insert(start.clone(), Self::Cost::zero());
+            // |V|-1 passes suffice to find every shortest path, so if the |V|th pass relaxes any
+            // edge there must be a negative cycle.
+            let mut last_relaxed = None;
+            for _ in 0..seen.len() {
+                last_relaxed = None;
+                for edge in edges.iter() {
+                    if let Some(next_cost) = relax(&costs, edge) {
+                        costs.insert(edge.dest().clone(), next_cost);
+                        routes.insert(edge.dest().clone(), edge.clone());
+                        last_relaxed = Some(edge.dest().clone());
+                    }
+                }
+                if last_relaxed.is_none() { break; }
+            }
+
+            if let Some(mut current) = last_relaxed {
+                let source = |n: &Self::Node| routes.get(n).expect("Must have a route").source().clone();
+                // Walking back |V| steps guarantees we're on the cycle itself
+                for _ in 0..seen.len() {
+                    current = source(&current);
+                }
+                let mut cycle = vec!(current.clone());
+                let mut next = source(&current);
+                while next != current {
+                    cycle.push(next.clone());
+                    next = source(&next);
+                }
+                cycle.reverse();
+                return Err(NegativeCycle(cycle));
+            }
+
+            Ok(walk_routes(&routes, start, goal))
+        }
+
+        // The structural algorithms below can't enumerate a Graph's nodes on their own, so they
+        // operate on the given nodes plus every node reachable from them.
+
+        // Components when edge direction is ignored, largest first
+        fn connected_components(&self, nodes: impl IntoIterator<Item=Self::Node>) -> Vec<HashSet<Self::Node>> {
+            let nodes = self.reachable(nodes);
+            let mut undirected: HashMap<_, Vec<_>> = HashMap::new();
+            for node in nodes.iter() {
+                for edge in self.neighbors(node) {
+                    undirected.entry(node.clone()).or_default().push(edge.dest().clone());
+                    undirected.entry(edge.dest().clone()).or_default().push(node.clone());
+                }
+            }
+
+            let mut seen = HashSet::new();
+            let mut components = Vec::new();
+            for node in nodes {
+                if !seen.insert(node.clone()) { continue; }
+                let mut component = HashSet::new();
+                let mut stack = vec!(node);
+                while let Some(current) = stack.pop() {
+                    for next in undirected.get(&current).into_iter().flatten() {
+                        if seen.insert(next.clone()) {
+                            stack.push(next.clone());
+                        }
+                    }
+                    component.insert(current);
+                }
+                components.push(component);
+            }
+            components.sort_by_key(|c| Reverse(c.len()));
+            components
+        }
+
+        // Tarjan's algorithm, iterative so large graphs don't overflow the stack. Components are
+        // returned in reverse topological order, i.e. no component has edges to a later one.
+        // https://en.wikipedia.org/wiki/Tarjan%27s_strongly_connected_components_algorithm
+        #[allow(dead_code)]
+        fn strongly_connected_components(&self, nodes: impl IntoIterator<Item=Self::Node>) -> Vec<Vec<Self::Node>> {
+            let mut indexes = HashMap::new();
+            let mut low_links: HashMap<Self::Node, usize> = HashMap::new();
+            let mut on_stack = HashSet::new();
+            let mut stack = Vec::new();
+            let mut components = Vec::new();
+
+            for root in nodes {
+                if indexes.contains_key(&root) { continue; }
+                // Each frame is a node and its not-yet-visited successors
+                let mut work = vec!((root.clone(), self.successors(&root)));
+                indexes.insert(root.clone(), indexes.len());
+                low_links.insert(root.clone(), indexes.len() - 1);
+                stack.push(root.clone());
+                on_stack.insert(root);
+
+                while let Some((node, successors)) = work.last_mut() {
+                    let node = node.clone();
+                    if let Some(next) = successors.pop() {
+                        if !indexes.contains_key(&next) {
+                            indexes.insert(next.clone(), indexes.len());
+                            low_links.insert(next.clone(), indexes.len() - 1);
+                            stack.push(next.clone());
+                            on_stack.insert(next.clone());
+                            let next_successors = self.successors(&next);
+                            work.push((next, next_successors));
+                        } else if on_stack.contains(&next) {
+                            let low = cmp::min(low_links[&node], indexes[&next]);
+                            low_links.insert(node, low);
+                        }
+                        continue;
+                    }
+
+                    work.pop();
+                    if let Some((parent, _)) = work.last() {
+                        let low = cmp::min(low_links[parent], low_links[&node]);
+                        low_links.insert(parent.clone(), low);
+                    }
+                    if low_links[&node] == indexes[&node] {
+                        let mut component = Vec::new();
+                        loop {
+                            let member = stack.pop().expect("Node must be on the stack");
+                            on_stack.remove(&member);
+                            let done = member == node;
+                            component.push(member);
+                            if done { break; }
+                        }
+                        component.reverse();
+                        components.push(component);
+                    }
+                }
+            }
+            components
+        }
+
+        // Kahn's algorithm; when several nodes are ready the smallest according to cmp goes first.
+        // Fails with one of the graph's cycles if no total ordering exists.
+        // https://en.wikipedia.org/wiki/Topological_sorting#Kahn's_algorithm
+        fn topological_sort_by<F>(&self, nodes: impl IntoIterator<Item=Self::Node>, mut cmp: F)
+                -> Result<Vec<Self::Node>, Cycle<Self::Node>>
+                where F: FnMut(&Self::Node, &Self::Node) -> Ordering {
+            let nodes = self.reachable(nodes);
+            let mut in_degrees: HashMap<_, usize> = nodes.iter().map(|n| (n.clone(), 0)).collect();
+            for node in nodes.iter() {
+                for edge in self.neighbors(node) {
+                    *in_degrees.get_mut(edge.dest()).expect("Reachable") += 1;
+                }
+            }
+
+            let mut ready: Vec<_> = nodes.iter().filter(|n| in_degrees[*n] == 0).cloned().collect();
+            let mut ordering = Vec::new();
+            while !ready.is_empty() {
+                let (index, _) = ready.iter().enumerate()
+                    .min_by(|(_, a), (_, b)| cmp(a, b))
+                    .expect("Not empty");
+                let node = ready.swap_remove(index);
+                for edge in self.neighbors(&node) {
+                    let degree = in_degrees.get_mut(edge.dest()).expect("Reachable");
+                    *degree -= 1;
+                    if *degree == 0 {
+                        ready.push(edge.dest().clone());
+                    }
+                }
+                ordering.push(node);
+            }
+
+            if ordering.len() < nodes.len() {
+                let remaining = nodes.into_iter().filter(|n| in_degrees[n] > 0);
+                return Err(self.find_cycle(remaining).expect("Unordered nodes must form a cycle"));
+            }
+            Ok(ordering)
+        }
+
+        // Returns a cycle as the sequence of nodes along it, e.g. [A, B, C] for A->B->C->A
+        fn find_cycle(&self, nodes: impl IntoIterator<Item=Self::Node>) -> Option<Cycle<Self::Node>> {
+            // Nodes in `path` are on the current DFS path, nodes in `done` have been fully explored
+            let mut done = HashSet::new();
+            for root in nodes {
+                if done.contains(&root) { continue; }
+                let mut path = vec!(root.clone());
+                let mut path_set: HashSet<_> = path.iter().cloned().collect();
+                let mut work = vec!(self.successors(&root));
+
+                while let Some(successors) = work.last_mut() {
+                    match successors.pop() {
+                        Some(next) => {
+                            if path_set.contains(&next) {
+                                let start = path.iter().position(|n| *n == next).expect("On path");
+                                return Some(Cycle(path.split_off(start)));
+                            }
+                            if done.contains(&next) { continue; }
+                            work.push(self.successors(&next));
+                            path_set.insert(next.clone());
+                            path.push(next);
+                        },
+                        None => {
+                            work.pop();
+                            let node = path.pop().expect("Path matches work");
+                            path_set.remove(&node);
+                            done.insert(node);
+                        }
+                    }
+                }
+            }
+            None
+        }
+
+        // Every node reachable from the given nodes (including themselves), in discovery order
+        fn reachable(&self, nodes: impl IntoIterator<Item=Self::Node>) -> Vec<Self::Node> {
+            let mut seen = HashSet::new();
+            let mut found = Vec::new();
+            let mut frontier: VecDeque<_> = nodes.into_iter().collect();
+            while let Some(current) = frontier.pop_front() {
+                if !seen.insert(current.clone()) { continue; }
+                frontier.extend(self.successors(&current));
+                found.push(current);
+            }
+            found
+        }
+
+        fn successors(&self, node: &Self::Node) -> Vec<Self::Node> {
+            self.neighbors(node).into_iter().map(|e| e.dest().clone()).collect()
+        }
+    }
+
+    // Graphs that can also be traversed backwards, which enables bidirectional searches
+    #[allow(dead_code)]
+    pub trait ReversibleGraph: Graph {
+        // Like neighbors() the returned edges point from the predecessor to the given node
+        fn predecessors(&self, dest: &Self::Node) -> Vec<Edge<Self::Node, Self::Cost>>;
+
+        // Like bfs(), but searches from both ends at once
+        fn bidirectional_bfs(&self, start: &Self::Node, goal: &Self::Node) -> Option<Vec<Self::Node>> {
+            // Each side maps the nodes it's reached to their distance and the next node back
+            // towards its origin
+            let mut forward: HashMap<_, _> = [(start.clone(), (0, start.clone()))].iter().cloned().collect();
+            let mut backward: HashMap<_, _> = [(goal.clone(), (0, goal.clone()))].iter().cloned().collect();
+            let mut forward_layer = vec!(start.clone());
+            let mut backward_layer = vec!(goal.clone());
+            let mut meeting = if start == goal { Some(start.clone()) } else { None };
+
+            while meeting.is_none() && !forward_layer.is_empty() && !backward_layer.is_empty() {
+                // Expand whichever side has less work to do; a full layer at a time so the
+                // shortest meeting point in that layer is found
+                let is_forward = forward_layer.len() <= backward_layer.len();
+                let (layer, seen, other) = if is_forward {
+                    (&mut forward_layer, &mut forward, &backward)
                 } else {
-                    return None;
+                    (&mut backward_layer, &mut backward, &forward)
+                };
+                let mut next_layer = Vec::new();
+                let mut best: Option<(usize, Self::Node)> = None;
+                for current in layer.iter() {
+                    let distance = seen[current].0 + 1;
+                    let nexts: Vec<_> = if is_forward {
+                        self.neighbors(current).into_iter().map(|e| e.dest().clone()).collect()
+                    } else {
+                        self.predecessors(current).into_iter().map(|e| e.source().clone()).collect()
+                    };
+                    for next in nexts {
+                        if seen.contains_key(&next) { continue; }
+                        seen.insert(next.clone(), (distance, current.clone()));
+                        if let Some((other_distance, _)) = other.get(&next) {
+                            if best.as_ref().is_none_or(|(d, _)| distance + other_distance < *d) {
+                                best = Some((distance + other_distance, next.clone()));
+                            }
+                        }
+                        next_layer.push(next);
+                    }
                 }
+                *layer = next_layer;
+                meeting = best.map(|(_, n)| n);
+            }
+
+            let meeting = meeting?;
+            let mut path = vec!(meeting.clone());
+            let mut current = meeting.clone();
+            while current != *start {
+                current = forward[&current].1.clone();
+                path.push(current.clone());
             }
             path.reverse();
+            let mut current = meeting;
+            while current != *goal {
+                current = backward[&current].1.clone();
+                path.push(current.clone());
+            }
+            Some(path)
+        }
+
+        // Like dijkstras(), but searches from both ends at once
+        // https://www.homepages.ucl.ac.uk/~ucahmto/math/2020/05/30/bidirectional-dijkstra.html
+        fn bidirectional_dijkstras(&self, start: &Self::Node, goal: &Self::Node) -> Option<Route<Self::Node, Self::Cost>> {
+            let zero = Self::Cost::zero();
+            if start == goal { return Some(Vec::new()); }
+            let mut frontiers = [BinaryHeap::new(), BinaryHeap::new()];
+            let mut costs = [HashMap::new(), HashMap::new()];
+            // Forward routes map nodes to the edge that reached them, backward routes to the edge
+            // leading from them towards the goal
+            let mut routes = [HashMap::new(), HashMap::new()];
+            for (side, node) in [start, goal].iter().enumerate() {
+                frontiers[side].push(State { cost: zero, node: (*node).clone() });
+                costs[side].insert((*node).clone(), zero);
+            }
+            // The cheapest complete route found so far, and the node the two sides met at
+            let mut best: Option<(Self::Cost, Self::Node)> = None;
+
+            loop {
+                let tops: Vec<_> = frontiers.iter().map(|f| f.peek().map(|s| s.cost)).collect();
+                let (forward_top, backward_top) = match (tops[0], tops[1]) {
+                    (Some(f), Some(b)) => (f, b),
+                    _ => break,
+                };
+                // Neither side can improve on the best route any more
+                if let Some((best_cost, _)) = best {
+                    if forward_top.plus(backward_top) >= best_cost { break; }
+                }
+
+                let side = if forward_top <= backward_top { 0 } else { 1 };
+                let State { cost, node: current } = frontiers[side].pop().expect("Peeked");
+                let current_cost = costs[side][&current];
+                if current_cost < cost { continue; } // stale entry
+                let edges = if side == 0 { self.neighbors(&current) } else { self.predecessors(&current) };
+                for edge in edges {
+                    assert!(edge.weight().partial_cmp(&zero) != Some(Ordering::Less),
+                            "Dijkstra's does not support negative edges: {:?}", edge);
+                    let next = if side == 0 { edge.dest() } else { edge.source() };
+                    let next_cost = current_cost.plus(edge.weight());
+                    let prior_next_cost = costs[side].get(next);
+                    if prior_next_cost.is_none() || *prior_next_cost.expect("Not-none") > next_cost {
+                        costs[side].insert(next.clone(), next_cost);
+                        frontiers[side].push(State { cost: next_cost, node: next.clone() });
+                        routes[side].insert(next.clone(), edge.clone());
+                        if let Some(other_cost) = costs[1 - side].get(next) {
+                            let total = next_cost.plus(*other_cost);
+                            if best.as_ref().is_none_or(|(b, _)| total < *b) {
+                                best = Some((total, next.clone()));
+                            }
+                        }
+                    }
+                }
+            }
+
+            let (_, meeting) = best?;
+            let mut path = walk_routes(&routes[0], start, &meeting).expect("Forward route must exist");
+            let mut current = meeting;
+            while current != *goal {
+                let edge = routes[1].get(&current).expect("Backward route must exist");
+                current = edge.dest().clone();
+                path.push(edge.clone());
+            }
             Some(path)
         }
     }
 
+    // Returns the new cost of the edge's destination if it's an improvement over the known cost
+    fn relax<N, C>(costs: &HashMap<N, C>, edge: &Edge<N, C>) -> Option<C>
+            where N: Clone + fmt::Debug + Eq + core::hash::Hash, C: Cost {
+        let next_cost = costs.get(edge.source())?.plus(edge.weight());
+        match costs.get(edge.dest()) {
+            Some(prior) if next_cost.partial_cmp(prior) != Some(Ordering::Less) => None,
+            _ => Some(next_cost),
+        }
+    }
+
+    fn walk_routes<N, C>(routes: &HashMap<N, Edge<N, C>>, start: &N, goal: &N) -> Option<Route<N, C>>
+            where N: Clone + fmt::Debug + Eq + core::hash::Hash, C: Cost {
+        let mut path = Vec::new();
+        let mut current = goal.clone();
+        while current != *start {
+            if let Some(next) = routes.get(&current) {
+                path.push(next.clone());
+                current = next.source().clone();
+            } else {
+                return None;
+            }
+        }
+        path.reverse();
+        Some(path)
+    }
+
     #[derive(Clone, Debug)]
-    pub struct Edge<N: Clone + std::fmt::Debug> {
-        weight: i32,
+    pub struct Edge<N: Clone + std::fmt::Debug, C: Cost> {
+        weight: C,
         source: N,
         dest: N,
     }
 
-    impl<N: Clone + std::fmt::Debug> Edge<N> {
-        pub fn new(weight: i32, source: N, dest: N) -> Edge<N> {
+    impl<N: Clone + std::fmt::Debug, C: Cost> Edge<N, C> {
+        pub fn new(weight: C, source: N, dest: N) -> Edge<N, C> {
             Edge { weight, source, dest }
         }
 
-        pub fn weight(&self) -> i32 { self.weight }
+        pub fn weight(&self) -> C { self.weight }
         pub fn source(&self) -> &N { &self.source }
         pub fn dest(&self) -> &N { &self.dest }
     }
 
-    impl<N: Copy + std::fmt::Debug> Copy for Edge<N> {}
+    impl<N: Copy + std::fmt::Debug, C: Cost> Copy for Edge<N, C> {}
+
+    // The edges traversed from a start node to a goal node
+    pub type Route<N, C> = Vec<Edge<N, C>>;
+
+    // The nodes of a negative-weight cycle, in traversal order
+    #[derive(Debug, Eq, PartialEq)]
+    #[allow(dead_code)]
+    pub struct NegativeCycle<N>(pub Vec<N>);
+
+    impl<N: fmt::Debug> fmt::Display for NegativeCycle<N> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "Negative cycle: {:?}", self.0)
+        }
+    }
+
+    impl<N: fmt::Debug> std::error::Error for NegativeCycle<N> {}
+
+    // The nodes of a cycle, in traversal order
+    #[derive(Debug, Eq, PartialEq)]
+    pub struct Cycle<N>(pub Vec<N>);
+
+    impl<N: fmt::Debug> fmt::Display for Cycle<N> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "Cycle: {:?}", self.0)
+        }
+    }
+
+    impl<N: fmt::Debug> std::error::Error for Cycle<N> {}
 
     #[derive(Clone, Debug)]
-    struct State<N: Clone + std::fmt::Debug> {
-        cost: i32,
+    struct State<N: Clone + std::fmt::Debug, C: Cost> {
+        cost: C,
         node: N,
     }
 
-    impl<N: Copy + std::fmt::Debug> Copy for State<N> {}
+    impl<N: Copy + std::fmt::Debug, C: Cost> Copy for State<N, C> {}
 
     // We don't implement Eq because it's not well defined, but Ord requires it exist
-    impl<N: Clone + std::fmt::Debug> PartialEq for State<N> {
+    impl<N: Clone + std::fmt::Debug, C: Cost> PartialEq for State<N, C> {
         fn eq(&self, _: &Self) -> bool {
             unimplemented!()
         }
     }
 
-    impl<N: Clone + std::fmt::Debug> Eq for State<N> {}
+    impl<N: Clone + std::fmt::Debug, C: Cost> Eq for State<N, C> {}
 
-    impl<N: Clone + std::fmt::Debug> Ord for State<N> {
-        fn cmp(&self, other: &State<N>) -> Ordering {
-            other.cost.cmp(&self.cost)
+    impl<N: Clone + std::fmt::Debug, C: Cost> Ord for State<N, C> {
+        fn cmp(&self, other: &State<N, C>) -> Ordering {
+            other.cost.partial_cmp(&self.cost).expect("Costs must be comparable")
         }
     }
 
-    impl<N: Clone + std::fmt::Debug> PartialOrd for State<N> {
-        fn partial_cmp(&self, other: &State<N>) -> Option<Ordering> {
+    impl<N: Clone + std::fmt::Debug, C: Cost> PartialOrd for State<N, C> {
+        fn partial_cmp(&self, other: &State<N, C>) -> Option<Ordering> {
             Some(self.cmp(other))
         }
     }
 }
-pub use self::pathfinding::{Edge,Graph};
+pub use self::pathfinding::{Cycle,Edge,Graph};
+
+mod cost {
+    use std::fmt;
+
+    // Edge weights need to be ordered and summed, but needn't be i32s. Floating-point costs work as
+    // long as they aren't NaN, and tuples compare lexicographically, e.g. (cost, tie_breaker).
+    pub trait Cost: Copy + fmt::Debug + PartialOrd {
+        fn zero() -> Self;
+        fn plus(self, other: Self) -> Self;
+    }
+
+    macro_rules! numeric_cost {
+        ($($t:ty),*) => { $(
+            impl Cost for $t {
+                fn zero() -> $t { 0 as $t }
+                fn plus(self, other: $t) -> $t { self + other }
+            }
+        )* };
+    }
+    numeric_cost!(i32, i64, u32, u64, usize, f32, f64);
+
+    impl<A: Cost, B: Cost> Cost for (A, B) {
+        fn zero() -> (A, B) { (A::zero(), B::zero()) }
+        fn plus(self, other: (A, B)) -> (A, B) { (self.0.plus(other.0), self.1.plus(other.1)) }
+    }
+
+    impl<A: Cost, B: Cost, C: Cost> Cost for (A, B, C) {
+        fn zero() -> (A, B, C) { (A::zero(), B::zero(), C::zero()) }
+        fn plus(self, other: (A, B, C)) -> (A, B, C) {
+            (self.0.plus(other.0), self.1.plus(other.1), self.2.plus(other.2))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn tuples() {
+            assert_eq!((1, 2).plus((3, 4)), (4, 6));
+            assert!((1, 5) < (2, 0));
+            assert_eq!(<(u64, f64)>::zero(), (0, 0.0));
+        }
+    }
+}
+pub use self::cost::Cost;
+
+mod grid {
+    use super::{Cost, Edge, Graph};
+    use itertools::Itertools;
+    use crate::euclid::{point, Point};
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    #[allow(dead_code)]
+    pub enum Connectivity {
+        Four,
+        Eight,
+    }
+
+    impl Connectivity {
+        // In reading order, i.e. top-to-bottom, left-to-right
+        fn neighbors(self, coord: Point) -> Box<dyn Iterator<Item = Point>> {
+            match self {
+                Connectivity::Four => Box::new(coord.neighbors4()),
+                Connectivity::Eight => Box::new(coord.neighbors8()),
+            }
+        }
+    }
+
+    // Adapts any 2D map into a Graph, given which points can be entered and the cost of stepping
+    // from one point to an adjacent one. E.g. for a HashSet<Point> of open squares:
+    //   GridGraph::new(|p| squares.contains(&p), |_, _| 1)
+    #[allow(dead_code)]
+    pub struct GridGraph<P, F, C> where P: Fn(Point) -> bool, F: Fn(Point, Point) -> C, C: Cost {
+        passable: P,
+        cost: F,
+        connectivity: Connectivity,
+        wrap: Option<(Point, Point)>,
+    }
+
+    impl<P, F, C> GridGraph<P, F, C> where P: Fn(Point) -> bool, F: Fn(Point, Point) -> C, C: Cost {
+        #[allow(dead_code)]
+        pub fn new(passable: P, cost: F) -> GridGraph<P, F, C> {
+            GridGraph { passable, cost, connectivity: Connectivity::Four, wrap: None }
+        }
+
+        #[allow(dead_code)]
+        pub fn with_connectivity(mut self, connectivity: Connectivity) -> GridGraph<P, F, C> {
+            self.connectivity = connectivity;
+            self
+        }
+
+        // Stepping off one edge of the (inclusive) bounds leads to the opposite edge
+        #[allow(dead_code)]
+        pub fn wrapping(mut self, min: Point, max: Point) -> GridGraph<P, F, C> {
+            assert!(min.x <= max.x);
+            assert!(min.y <= max.y);
+            self.wrap = Some((min, max));
+            self
+        }
+
+        fn wrap(&self, coord: Point) -> Point {
+            match self.wrap {
+                Some((min, max)) => point(
+                    min.x + (coord.x - min.x).rem_euclid(max.x - min.x + 1),
+                    min.y + (coord.y - min.y).rem_euclid(max.y - min.y + 1)),
+                None => coord,
+            }
+        }
+    }
+
+    impl<P, F, C> Graph for GridGraph<P, F, C> where P: Fn(Point) -> bool, F: Fn(Point, Point) -> C, C: Cost {
+        type Node = Point;
+        type Cost = C;
+
+        fn neighbors(&self, source: &Point) -> Vec<Edge<Point, C>> {
+            self.connectivity.neighbors(*source)
+                .map(|p| self.wrap(p))
+                .filter(|p| p != source && (self.passable)(*p))
+                // Wrapping a narrow grid can reach the same point from multiple directions
+                .unique()
+                .map(|p| Edge::new((self.cost)(*source, p), *source, p))
+                .collect()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::collections::HashSet;
+
+        fn walls(s: &str) -> HashSet<Point> {
+            s.lines().enumerate()
+                .flat_map(|(y, l)| l.chars().enumerate()
+                    .filter(|&(_, c)| c == '#').map(move |(x, _)| point(x as i32, y as i32)))
+                .collect()
+        }
+
+        #[test]
+        fn four_connected() {
+            let walls = walls(".....\n.....\n.....\n#####\n.....");
+            let graph = GridGraph::new(|p: Point| p.in_bounds(point(0, 0), point(4, 4)) && !walls.contains(&p), |_, _| 1);
+            assert_eq!(graph.bfs(&point(1, 1), &point(3, 4)), None);
+
+            let graph = GridGraph::new(|p: Point| !walls.contains(&p), |_, _| 1);
+            assert_eq!(graph.bfs(&point(1, 1), &point(3, 4)).unwrap().len(), 10);
+            assert_eq!(graph.dijkstras(&point(1, 1), &point(3, 4)).unwrap().len(), 9);
+        }
+
+        #[test]
+        fn eight_connected() {
+            let graph = GridGraph::new(|_| true, |_, _| 1).with_connectivity(Connectivity::Eight);
+            assert_eq!(graph.dijkstras(&point(0, 0), &point(3, 5)).unwrap().len(), 5);
+            assert_eq!(graph.neighbors(&point(0, 0)).len(), 8);
+        }
+
+        #[test]
+        fn weighted() {
+            // Moving down is expensive
+            let graph = GridGraph::new(|p: Point| p.in_bounds(point(0, 0), point(9, 9)),
+                                       |a: Point, b: Point| if b.y > a.y { 10 } else { 1 });
+            let route = graph.dijkstras(&point(0, 0), &point(0, 2)).unwrap();
+            assert_eq!(route.iter().map(|e| e.weight()).sum::<i32>(), 20);
+            let route = graph.dijkstras(&point(0, 2), &point(0, 0)).unwrap();
+            assert_eq!(route.iter().map(|e| e.weight()).sum::<i32>(), 2);
+        }
+
+        #[test]
+        fn wrapping() {
+            let graph = GridGraph::new(|_| true, |_, _| 1).wrapping(point(0, 0), point(9, 4));
+            assert_eq!(graph.dijkstras(&point(0, 0), &point(9, 4)).unwrap().len(), 2);
+            assert_eq!(graph.neighbors(&point(0, 0)).iter().map(|e| *e.dest()).collect::<Vec<_>>(),
+                       vec!(point(0, 4), point(9, 0), point(1, 0), point(0, 1)));
+
+            // A one-wide grid can only step to itself horizontally
+            let graph = GridGraph::new(|_| true, |_, _| 1).wrapping(point(0, 0), point(0, 4));
+            assert_eq!(graph.neighbors(&point(0, 2)).len(), 2);
+        }
+
+        #[test]
+        fn a_star() {
+            let walls = walls("..........\n.########.\n........#.\n.######.#.\n..........");
+            let graph = GridGraph::new(|p: Point| p.in_bounds(point(0, 0), point(9, 4)) && !walls.contains(&p), |_, _| 1);
+            let goal = point(7, 2);
+            let manhattan = |p: &Point| (goal - *p).grid_len() as i32;
+            let a_star = graph.a_star(&point(0, 0), &goal, manhattan).unwrap();
+            let dijkstras = graph.dijkstras(&point(0, 0), &goal).unwrap();
+            assert_eq!(a_star.len(), dijkstras.len());
+            assert_eq!(a_star.len(), 9);
+        }
+    }
+}
+
+// Optional instrumentation for the searches above, for when a route is surprising (or missing)
+mod trace {
+    use std::collections::{HashMap, HashSet};
+    use std::fmt::{self, Debug, Write};
+    use std::hash::Hash;
+    use crate::euclid::{point, Point};
+
+    #[derive(Debug)]
+    pub struct SearchTrace<N> {
+        enabled: bool,
+        // Snapshot the frontier before every nth expansion; None to skip them
+        snapshot_every: Option<usize>,
+        steps: usize,
+        visited: Vec<N>,
+        reached: Vec<(N, N)>,
+        frontiers: Vec<Vec<N>>,
+        path: Option<Vec<N>>,
+    }
+
+    impl<N: Clone + Debug + Eq + Hash> SearchTrace<N> {
+        pub fn new() -> SearchTrace<N> {
+            SearchTrace { enabled: true, snapshot_every: None, steps: 0, visited: Vec::new(),
+                reached: Vec::new(), frontiers: Vec::new(), path: None }
+        }
+
+        // Used by the un-traced searches, records nothing
+        pub fn disabled() -> SearchTrace<N> {
+            SearchTrace { enabled: false, ..SearchTrace::new() }
+        }
+
+        #[allow(dead_code)]
+        pub fn with_frontier_snapshots(mut self, every: usize) -> SearchTrace<N> {
+            assert!(every > 0);
+            self.snapshot_every = Some(every);
+            self
+        }
+
+        // Nodes in the order they were expanded
+        #[allow(dead_code)]
+        pub fn visited(&self) -> &[N] { &self.visited }
+        // (source, dest) pairs, in the order each dest was (re-)reached
+        #[allow(dead_code)]
+        pub fn reached(&self) -> &[(N, N)] { &self.reached }
+        #[allow(dead_code)]
+        pub fn frontiers(&self) -> &[Vec<N>] { &self.frontiers }
+        // The nodes of the final route, or None if the goal wasn't reachable
+        #[allow(dead_code)]
+        pub fn path(&self) -> Option<&[N]> { self.path.as_deref() }
+
+        pub(super) fn visit(&mut self, node: &N) {
+            if self.enabled {
+                self.visited.push(node.clone());
+            }
+        }
+
+        pub(super) fn reach(&mut self, source: &N, dest: &N) {
+            if self.enabled {
+                self.reached.push((source.clone(), dest.clone()));
+            }
+        }
+
+        pub(super) fn frontier<'a>(&mut self, frontier: impl Iterator<Item=&'a N>) where N: 'a {
+            if !self.enabled { return; }
+            if let Some(every) = self.snapshot_every {
+                if self.steps.is_multiple_of(every) {
+                    self.frontiers.push(frontier.cloned().collect());
+                }
+            }
+            self.steps += 1;
+        }
+
+        pub(super) fn finish(&mut self, path: Option<&[N]>) {
+            if self.enabled {
+                self.path = path.map(|p| p.to_vec());
+            }
+        }
+
+        // Projects the trace onto different nodes, e.g. to render a search whose nodes include
+        // more than just a Point
+        #[allow(dead_code)]
+        pub fn map_nodes<M, F>(&self, f: F) -> SearchTrace<M> where F: Fn(&N) -> M {
+            SearchTrace {
+                enabled: self.enabled,
+                snapshot_every: self.snapshot_every,
+                steps: self.steps,
+                visited: self.visited.iter().map(&f).collect(),
+                reached: self.reached.iter().map(|(s, d)| (f(s), f(d))).collect(),
+                frontiers: self.frontiers.iter().map(|fr| fr.iter().map(&f).collect()).collect(),
+                path: self.path.as_ref().map(|p| p.iter().map(&f).collect()),
+            }
+        }
+
+        // https://graphviz.org/doc/info/lang.html
+        // Visited nodes are grey, the last frontier snapshot is blue, and the path is red
+        #[allow(dead_code)]
+        pub fn to_dot(&self) -> String {
+            let path: Vec<_> = self.path.iter().flatten().collect();
+            let path_edges: HashSet<_> = path.windows(2).map(|w| (w[0], w[1])).collect();
+            let frontier: HashSet<_> = self.frontiers.last().into_iter().flatten().collect();
+            let visited: HashSet<_> = self.visited.iter().collect();
+
+            let mut out = String::new();
+            out.push_str("digraph search {\n");
+            let mut seen = HashSet::new();
+            let nodes = self.visited.iter().chain(self.reached.iter().map(|(_, d)| d))
+                .chain(path.iter().cloned());
+            for node in nodes {
+                if !seen.insert(node) { continue; }
+                let color = if path.contains(&node) { "red" }
+                    else if frontier.contains(node) { "lightblue" }
+                    else if visited.contains(node) { "grey" }
+                    else { "white" };
+                writeln!(out, "  {} [style=filled, fillcolor={}];", dot_id(node), color)
+                    .expect("writing to string");
+            }
+            // Only the most recent route to each node is interesting
+            let mut latest = HashMap::new();
+            for (source, dest) in self.reached.iter() {
+                latest.insert(dest, source);
+            }
+            let mut written = HashSet::new();
+            for (_, dest) in self.reached.iter() {
+                if !written.insert(dest) { continue; }
+                let source = latest[dest];
+                let attrs = if path_edges.contains(&(source, dest)) { " [color=red, penwidth=2]" } else { "" };
+                writeln!(out, "  {} -> {}{};", dot_id(source), dot_id(dest), attrs)
+                    .expect("writing to string");
+            }
+            out.push_str("}\n");
+            out
+        }
+    }
+
+    impl<N: Clone + Debug + Eq + Hash> Default for SearchTrace<N> {
+        fn default() -> Self { SearchTrace::new() }
+    }
+
+    fn dot_id<N: Debug>(node: &N) -> String {
+        format!("{:?}", format!("{:?}", node)) // Debug-formatting a String quotes and escapes it
+    }
+
+    impl SearchTrace<Point> {
+        // Overlays the search on a map, where base supplies the character for each point. The path
+        // is drawn as '*', the last frontier snapshot as '?', and other visited points as 'o'.
+        pub fn to_ascii(&self, base: impl Fn(Point) -> char) -> String {
+            let path: HashSet<_> = self.path.iter().flatten().collect();
+            let frontier: HashSet<_> = self.frontiers.last().into_iter().flatten().collect();
+            let visited: HashSet<_> = self.visited.iter().collect();
+            let bounds = Point::bounding_box(
+                self.visited.iter().chain(path.iter().cloned()).chain(frontier.iter().cloned()).cloned());
+
+            let mut out = String::new();
+            if let Some((min, max)) = bounds {
+                for y in min.y..max.y+1 {
+                    for x in min.x..max.x+1 {
+                        let coord = point(x, y);
+                        out.push(
+                            if path.contains(&coord) { '*' }
+                            else if frontier.contains(&coord) { '?' }
+                            else if visited.contains(&coord) { 'o' }
+                            else { base(coord) });
+                    }
+                    out.push('\n');
+                }
+            }
+            out
+        }
+    }
+
+    impl fmt::Display for SearchTrace<Point> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.to_ascii(|_| ' '))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::pathfinding::Graph;
+        use crate::pathfinding::grid::GridGraph;
+
+        #[test]
+        fn disabled() {
+            let graph = GridGraph::new(|_| true, |_, _| 1);
+            let mut trace = SearchTrace::disabled();
+            graph.dijkstras_traced(&point(0, 0), &point(2, 0), &mut trace);
+            assert!(trace.visited().is_empty());
+            assert_eq!(trace.path(), None);
+        }
+
+        #[test]
+        fn ascii() {
+            let open = |p: Point| p.in_bounds(point(0, 0), point(4, 2)) && p != point(2, 0) && p != point(2, 1);
+            let graph = GridGraph::new(open, |_, _| 1);
+            let mut trace = SearchTrace::new();
+            let route = graph.bfs_traced(&point(0, 0), &point(4, 0), &mut trace).unwrap();
+            assert_eq!(trace.path(), Some(&route[..]));
+            assert_eq!(trace.to_ascii(|p| if open(p) { '.' } else { '#' }),
+                       "**#**\no*#*.\no***o\n");
+        }
+
+        #[test]
+        fn frontiers() {
+            let graph = GridGraph::new(|p: Point| p.in_bounds(point(0, 0), point(9, 0)), |_, _| 1);
+            let mut trace = SearchTrace::new().with_frontier_snapshots(2);
+            graph.dijkstras_traced(&point(0, 0), &point(4, 0), &mut trace);
+            assert_eq!(trace.frontiers(), &[vec!(point(0, 0)), vec!(point(2, 0)), vec!(point(4, 0))]);
+            assert_eq!(trace.visited().len(), 4);
+            assert_eq!(trace.to_ascii(|_| '.'), "*****\n");
+        }
+
+        #[test]
+        fn unreachable() {
+            let graph = GridGraph::new(|p: Point| p.in_bounds(point(0, 0), point(2, 0)), |_, _| 1);
+            let mut trace = SearchTrace::new();
+            assert!(graph.dijkstras_traced(&point(0, 0), &point(5, 0), &mut trace).is_none());
+            assert_eq!(trace.path(), None);
+            assert_eq!(trace.to_string(), "ooo\n");
+        }
+
+        #[test]
+        fn dot() {
+            let graph = GridGraph::new(|p: Point| p.in_bounds(point(0, 0), point(2, 0)), |_, _| 1);
+            let mut trace = SearchTrace::new();
+            graph.dijkstras_traced(&point(0, 0), &point(1, 0), &mut trace);
+            let projected = trace.map_nodes(|p| p.x);
+            assert_eq!(projected.to_dot(),
+                       "digraph search {\n  \"0\" [style=filled, fillcolor=red];\n  \
+                       \"1\" [style=filled, fillcolor=red];\n  \"0\" -> \"1\" [color=red, penwidth=2];\n}\n");
+        }
+    }
+}
+pub use self::trace::SearchTrace;
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::pathfinding::ReversibleGraph;
     use crate::euclid::{point,Point,vector};
-    use std::collections::HashSet;
+    use std::collections::{HashMap, HashSet};
 
     struct BasicGraph {
         blocked: HashSet<Point>,
@@ -152,8 +1105,9 @@ mod tests {
 
     impl Graph for BasicGraph {
         type Node = Point;
+        type Cost = i32;
 
-        fn neighbors(&self, source: &Self::Node) -> Vec<Edge<Self::Node>> {
+        fn neighbors(&self, source: &Self::Node) -> Vec<Edge<Self::Node, Self::Cost>> {
             if self.blocked.contains(source) { return vec!(); }
 
             vec!(vector(0, 1), vector(1, 0), vector(0, -1), vector(-1, 0)).iter()
@@ -164,6 +1118,15 @@ mod tests {
         }
     }
 
+    impl ReversibleGraph for BasicGraph {
+        // Edges are symmetric
+        fn predecessors(&self, dest: &Self::Node) -> Vec<Edge<Self::Node, Self::Cost>> {
+            self.neighbors(dest).into_iter()
+                .map(|e| Edge::new(e.weight(), *e.dest(), *e.source()))
+                .collect()
+        }
+    }
+
     #[test]
     fn direct() {
         let graph = BasicGraph::new(&vec!());
@@ -199,4 +1162,207 @@ mod tests {
         assert_eq!(djk_route[0].source(), &start);
         assert_eq!(djk_route[djk_route.len()-1].dest(), &goal);
     }
-}
\ No newline at end of file
+
+    struct WeightedGraph<C: Cost> {
+        edges: HashMap<char, Vec<(char, C)>>,
+    }
+
+    impl<C: Cost> WeightedGraph<C> {
+        fn new(edges: &[(char, char, C)]) -> WeightedGraph<C> {
+            let mut map = HashMap::new();
+            for &(source, dest, weight) in edges {
+                map.entry(source).or_insert_with(Vec::new).push((dest, weight));
+            }
+            WeightedGraph { edges: map }
+        }
+    }
+
+    impl<C: Cost> Graph for WeightedGraph<C> {
+        type Node = char;
+        type Cost = C;
+
+        fn neighbors(&self, source: &char) -> Vec<Edge<char, C>> {
+            self.edges.get(source).into_iter().flatten()
+                .map(|&(dest, weight)| Edge::new(weight, *source, dest))
+                .collect()
+        }
+    }
+
+    impl<C: Cost> ReversibleGraph for WeightedGraph<C> {
+        fn predecessors(&self, dest: &char) -> Vec<Edge<char, C>> {
+            self.edges.iter()
+                .flat_map(|(&source, edges)| edges.iter()
+                    .filter(|&&(d, _)| d == *dest)
+                    .map(move |&(d, weight)| Edge::new(weight, source, d)))
+                .collect()
+        }
+    }
+
+    fn nodes<C: Cost>(path: &[Edge<char, C>]) -> String {
+        path.iter().map(|e| *e.source()).chain(path.last().map(|e| *e.dest())).collect()
+    }
+
+    #[test]
+    fn unsigned_costs() {
+        let graph = WeightedGraph::new(&[
+            ('A', 'B', 5_000_000_000_u64), ('A', 'C', 1), ('C', 'D', 1), ('D', 'B', 1)]);
+        let route = graph.dijkstras(&'A', &'B').unwrap();
+        assert_eq!(nodes(&route), "ACDB");
+        assert_eq!(route.iter().map(|e| e.weight()).sum::<u64>(), 3);
+    }
+
+    #[test]
+    fn float_costs() {
+        let graph = WeightedGraph::new(&[('A', 'B', 1.5), ('A', 'C', 0.5), ('C', 'B', 0.75)]);
+        assert_eq!(nodes(&graph.dijkstras(&'A', &'B').unwrap()), "ACB");
+    }
+
+    #[test]
+    fn tuple_costs() {
+        // Same primary cost, the tie-breaker decides
+        let graph = WeightedGraph::new(&[
+            ('A', 'B', (1, 5)), ('B', 'D', (1, 5)), ('A', 'C', (1, 1)), ('C', 'D', (1, 1))]);
+        assert_eq!(nodes(&graph.dijkstras(&'A', &'D').unwrap()), "ACD");
+    }
+
+    #[test]
+    #[should_panic(expected = "negative edges")]
+    fn dijkstras_negative() {
+        let graph = WeightedGraph::new(&[('A', 'B', 4), ('A', 'C', 5), ('C', 'B', -3)]);
+        graph.dijkstras(&'A', &'Z'); // unreachable, so every edge is explored
+    }
+
+    #[test]
+    fn bellman_ford() {
+        let graph = WeightedGraph::new(&[('A', 'B', 4), ('A', 'C', 5), ('C', 'B', -3), ('B', 'D', 1)]);
+        let route = graph.bellman_ford(&'A', &'D').unwrap().unwrap();
+        assert_eq!(nodes(&route), "ACBD");
+        assert_eq!(route.iter().map(|e| e.weight()).sum::<i32>(), 3);
+
+        assert_eq!(graph.bellman_ford(&'D', &'A').unwrap().map(|r| r.len()), None);
+        assert_eq!(graph.bellman_ford(&'A', &'A').unwrap().map(|r| r.len()), Some(0));
+    }
+
+    #[test]
+    fn bellman_ford_agrees() {
+        let graph = WeightedGraph::new(&[
+            ('A', 'B', 7), ('A', 'C', 9), ('A', 'F', 14), ('B', 'C', 10), ('B', 'D', 15),
+            ('C', 'D', 11), ('C', 'F', 2), ('D', 'E', 6), ('F', 'E', 9)]);
+        let route = graph.bellman_ford(&'A', &'E').unwrap().unwrap();
+        assert_eq!(nodes(&route), nodes(&graph.dijkstras(&'A', &'E').unwrap()));
+        assert_eq!(nodes(&route), "ACFE");
+    }
+
+    #[test]
+    fn negative_cycle() {
+        let graph = WeightedGraph::new(&[
+            ('A', 'B', 1), ('B', 'C', 2), ('C', 'D', -4), ('D', 'B', 1), ('D', 'E', 1)]);
+        let cycle = graph.bellman_ford(&'A', &'E').unwrap_err().0;
+        assert_eq!(cycle.len(), 3);
+        let start = cycle.iter().position(|&n| n == 'B').unwrap();
+        let rotated: String = cycle[start..].iter().chain(cycle[..start].iter()).collect();
+        assert_eq!(rotated, "BCD");
+    }
+
+    fn manhattan(goal: Point) -> impl Fn(&Point) -> i32 {
+        move |p| (goal - *p).grid_len() as i32
+    }
+
+    parameterized_test::create!{ search_agrees, (blocked, start, goal), {
+        let graph = BasicGraph::new(&blocked);
+        let expected = graph.dijkstras(&start, &goal).map(|r| r.len());
+
+        assert_eq!(graph.bidirectional_bfs(&start, &goal).map(|r| r.len() - 1), expected);
+        let bidi = graph.bidirectional_dijkstras(&start, &goal).unwrap();
+        assert_eq!(Some(bidi.len()), expected);
+        assert!(bidi.windows(2).all(|w| w[0].dest() == w[1].source()));
+
+        let ida = graph.ida_star(&start, &goal, manhattan(goal)).unwrap();
+        assert_eq!(Some(ida.len()), expected);
+        assert!(ida.windows(2).all(|w| w[0].dest() == w[1].source()));
+    }}
+    search_agrees! {
+        same: (vec!(), point(1, 1), point(1, 1)),
+        direct: (vec!(), point(1, 1), point(3, 4)),
+        wall: (vec!(point(0, 3), point(1, 3), point(2, 3), point(3, 3), point(4, 3)),
+               point(1, 1), point(3, 4)),
+        cup: (vec!(point(-1, 2), point(0, 2), point(1, 2), point(-1, 1), point(1, 1)),
+              point(0, 1), point(0, 4)),
+    }
+
+    #[test]
+    fn weighted_variants_agree() {
+        let graph = WeightedGraph::new(&[
+            ('A', 'B', 7), ('A', 'C', 9), ('A', 'F', 14), ('B', 'C', 10), ('B', 'D', 15),
+            ('C', 'D', 11), ('C', 'F', 2), ('D', 'E', 6), ('F', 'E', 9)]);
+        let expected = nodes(&graph.dijkstras(&'A', &'E').unwrap());
+        assert_eq!(nodes(&graph.bidirectional_dijkstras(&'A', &'E').unwrap()), expected);
+        assert_eq!(nodes(&graph.ida_star(&'A', &'E', |_| 0).unwrap()), expected);
+
+        assert_eq!(graph.bidirectional_dijkstras(&'E', &'A').map(|r| r.len()), None);
+        assert_eq!(graph.bidirectional_bfs(&'E', &'A'), None);
+        assert_eq!(graph.ida_star(&'E', &'A', |_| 0).map(|r| r.len()), None);
+    }
+
+    fn unweighted(edges: &[(char, char)]) -> WeightedGraph<i32> {
+        WeightedGraph::new(&edges.iter().map(|&(s, d)| (s, d, 1)).collect::<Vec<_>>())
+    }
+
+    fn sorted(nodes: impl IntoIterator<Item=char>) -> String {
+        let mut nodes: Vec<_> = nodes.into_iter().collect();
+        nodes.sort();
+        nodes.into_iter().collect()
+    }
+
+    #[test]
+    fn connected_components() {
+        let graph = unweighted(&[('8', '6'), ('3', '4'), ('5', '2'), ('1', '8'), ('2', '6')]);
+        let components: Vec<_> = graph.connected_components("12345678".chars())
+            .into_iter().map(sorted).collect();
+        assert_eq!(components, vec!("12568", "34", "7"));
+    }
+
+    #[test]
+    fn strongly_connected_components() {
+        let graph = unweighted(&[
+            ('A', 'B'), ('B', 'C'), ('C', 'A'), ('C', 'D'), ('D', 'E'), ('E', 'D'), ('E', 'F')]);
+        let components: Vec<_> = graph.strongly_connected_components("ABCDEF".chars())
+            .into_iter().map(sorted).collect();
+        // reverse topological order
+        assert_eq!(components, vec!("F", "DE", "ABC"));
+    }
+
+    #[test]
+    fn topological_sort() {
+        let graph = unweighted(&[
+            ('C', 'A'), ('C', 'F'), ('A', 'B'), ('A', 'D'), ('B', 'E'), ('D', 'E'), ('F', 'E')]);
+        let ordering: String = graph.topological_sort_by("ABCDEF".chars(), |a, b| a.cmp(b)).unwrap()
+            .into_iter().collect();
+        assert_eq!(ordering, "CABDFE");
+        let ordering: String = graph.topological_sort_by("ABCDEF".chars(), |a, b| b.cmp(a)).unwrap()
+            .into_iter().collect();
+        assert_eq!(ordering, "CFADBE");
+    }
+
+    #[test]
+    fn topological_sort_cycle() {
+        let graph = unweighted(&[('A', 'B'), ('B', 'C'), ('C', 'D'), ('D', 'B'), ('A', 'E')]);
+        let cycle = graph.topological_sort_by("A".chars(), |a, b| a.cmp(b)).unwrap_err();
+        assert_eq!(sorted(cycle.0), "BCD");
+    }
+
+    #[test]
+    fn find_cycle() {
+        let graph = unweighted(&[('A', 'B'), ('B', 'C'), ('A', 'C')]);
+        assert_eq!(graph.find_cycle("ABC".chars()), None);
+
+        let graph = unweighted(&[('A', 'B'), ('B', 'C'), ('C', 'D'), ('D', 'B')]);
+        let Cycle(cycle) = graph.find_cycle("A".chars()).unwrap();
+        let start = cycle.iter().position(|&n| n == 'B').unwrap();
+        let rotated: String = cycle[start..].iter().chain(cycle[..start].iter()).collect();
+        assert_eq!(rotated, "BCD");
+
+        let graph = unweighted(&[('A', 'A')]);
+        assert_eq!(graph.find_cycle("A".chars()), Some(Cycle(vec!('A'))));
+    }
+}